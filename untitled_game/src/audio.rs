@@ -0,0 +1,179 @@
+//! Music and sound effects. Every sound plays on the music or the effects bus, the gain of a bus
+//! is the master `Volume` times its own volume setting.
+
+use crate::{GameState, MusicVolume, Player, SfxVolume, Volume};
+use avian3d::prelude::*;
+use bevy::audio::Volume as Gain;
+use bevy::prelude::*;
+
+pub fn audio_plugin(app: &mut App) {
+    app.add_event::<PlaySound>()
+        .add_systems(Startup, load_sounds)
+        .add_systems(OnEnter(GameState::Menu), play_menu_music)
+        .add_systems(OnEnter(GameState::Game), play_game_music)
+        .add_systems(
+            Update,
+            (
+                impact_sounds,
+                play_sounds,
+                update_music_volume
+                    .run_if(resource_changed::<Volume>.or(resource_changed::<MusicVolume>)),
+            )
+                .chain(),
+        );
+}
+
+const MENU_MUSIC: &str = "audio/menu_music.ogg";
+const GAME_MUSIC: &str = "audio/game_music.ogg";
+
+// Collisions slower than this don't make a sound, otherwise resting bodies keep clicking
+const MIN_IMPACT_SPEED: f32 = 1.0;
+// Collisions this fast and faster play at full volume
+const MAX_IMPACT_SPEED: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sound {
+    Click,
+    Impact,
+}
+
+// Send to play a sound effect once, `volume` is relative to the effects bus
+#[derive(Event, Debug)]
+pub struct PlaySound {
+    pub sound: Sound,
+    pub volume: f32,
+}
+
+impl PlaySound {
+    pub fn new(sound: Sound) -> Self {
+        Self { sound, volume: 1. }
+    }
+}
+
+#[derive(Resource)]
+struct SoundHandles {
+    click: Handle<AudioSource>,
+    impact: Handle<AudioSource>,
+}
+
+// Tag component used to tag the background music, there is only one at a time
+#[derive(Component)]
+struct MusicTrack;
+
+fn bus_gain(master: Volume, bus: u32) -> f32 {
+    master.0 as f32 / 9. * bus as f32 / 9.
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SoundHandles {
+        click: asset_server.load("audio/click.wav"),
+        impact: asset_server.load("audio/impact.wav"),
+    });
+}
+
+fn play_menu_music(
+    commands: Commands,
+    asset_server: Res<AssetServer>,
+    volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    tracks: Query<Entity, With<MusicTrack>>,
+) {
+    play_music(
+        MENU_MUSIC,
+        commands,
+        &asset_server,
+        *volume,
+        *music_volume,
+        tracks,
+    );
+}
+
+fn play_game_music(
+    commands: Commands,
+    asset_server: Res<AssetServer>,
+    volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    tracks: Query<Entity, With<MusicTrack>>,
+) {
+    play_music(
+        GAME_MUSIC,
+        commands,
+        &asset_server,
+        *volume,
+        *music_volume,
+        tracks,
+    );
+}
+
+// Replaces the current music with `path`, looped
+fn play_music(
+    path: &'static str,
+    mut commands: Commands,
+    asset_server: &AssetServer,
+    volume: Volume,
+    music_volume: MusicVolume,
+    tracks: Query<Entity, With<MusicTrack>>,
+) {
+    for entity in &tracks {
+        commands.entity(entity).despawn();
+    }
+    commands.spawn((
+        Name::new("Music"),
+        AudioPlayer::new(asset_server.load(path)),
+        PlaybackSettings::LOOP.with_volume(Gain::new(bus_gain(volume, music_volume.0))),
+        MusicTrack,
+    ));
+}
+
+fn update_music_volume(
+    volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    sinks: Query<&AudioSink, With<MusicTrack>>,
+) {
+    for sink in &sinks {
+        sink.set_volume(bus_gain(*volume, music_volume.0));
+    }
+}
+
+fn play_sounds(
+    mut commands: Commands,
+    mut events: EventReader<PlaySound>,
+    handles: Res<SoundHandles>,
+    volume: Res<Volume>,
+    sfx_volume: Res<SfxVolume>,
+) {
+    let gain = bus_gain(*volume, sfx_volume.0);
+    for event in events.read() {
+        let source = match event.sound {
+            Sound::Click => handles.click.clone(),
+            Sound::Impact => handles.impact.clone(),
+        };
+        commands.spawn((
+            AudioPlayer::new(source),
+            PlaybackSettings::DESPAWN.with_volume(Gain::new(gain * event.volume)),
+        ));
+    }
+}
+
+// Dynamic bodies hitting anything, louder the faster they were going
+fn impact_sounds(
+    mut collisions: EventReader<CollisionStarted>,
+    bodies: Query<(&RigidBody, &LinearVelocity), Without<Player>>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    for CollisionStarted(a, b) in collisions.read() {
+        let speed = [a, b]
+            .into_iter()
+            .filter_map(|entity| bodies.get(*entity).ok())
+            .filter(|(rigid_body, _)| rigid_body.is_dynamic())
+            .map(|(_, velocity)| velocity.length())
+            .fold(0., f32::max);
+        if speed < MIN_IMPACT_SPEED {
+            continue;
+        }
+        sounds.send(PlaySound {
+            sound: Sound::Impact,
+            volume: (speed / MAX_IMPACT_SPEED).min(1.),
+        });
+    }
+}
@@ -0,0 +1,262 @@
+//! Keeps everything inside the [`WorldBounds`]. The player starts at the level's [`PlayerSpawn`]
+//! and is respawned at the last checkpoint reached, or else at that spawn, other dynamic bodies
+//! are despawned or put back where they started. Spawns and checkpoints are set up in Blender with
+//! custom properties on an empty, `{"player_spawn": true}` or `{"checkpoint": {"radius": 2}}`, or
+//! with the reflected components, `{"PlayerSpawn": {}}` or `{"Checkpoint": {"radius": 2}}`
+
+use crate::extras::{Extras, ExtrasReader, ExtrasReady};
+use crate::menu::MenuState;
+use crate::spawner::Spawned;
+use crate::{GameState, Player};
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn bounds_plugin(app: &mut App) {
+    app.init_resource::<WorldBounds>()
+        .init_resource::<LastCheckpoint>()
+        .init_resource::<StartPosition>()
+        .register_type::<PlayerSpawn>()
+        .register_type::<Checkpoint>()
+        .add_observer(spawn_markers)
+        .add_systems(OnExit(GameState::Game), reset_respawn)
+        .add_systems(Update, (record_body_homes, place_player))
+        .add_systems(
+            Update,
+            (
+                record_start_position,
+                reach_checkpoints,
+                respawn_player,
+                out_of_bounds_bodies,
+            )
+                .chain()
+                .run_if(in_state(MenuState::Disabled)),
+        );
+}
+
+#[derive(Resource, Debug)]
+pub struct WorldBounds {
+    // The bottom of the bounds is the kill plane
+    pub min: Vec3,
+    pub max: Vec3,
+    // Bodies of the level leaving the bounds are put back where they started instead of being
+    // despawned, spawned ones are always despawned as their spawner makes new ones
+    pub recycle_bodies: bool,
+    // Height above the respawn point the player is dropped from
+    pub respawn_height: f32,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            min: Vec3::new(-200., -20., -200.),
+            max: Vec3::new(200., 200., 200.),
+            recycle_bodies: true,
+            respawn_height: 1.,
+        }
+    }
+}
+
+impl WorldBounds {
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+// Where the player respawns when there is no checkpoint
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+pub struct PlayerSpawn;
+
+// Given to the player when the game starts, until it is moved to the level's `PlayerSpawn`
+#[derive(Component, Debug)]
+pub struct AwaitingSpawn;
+
+// Becomes the respawn point once the player comes within its radius
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct Checkpoint {
+    pub radius: f32,
+}
+
+impl Default for Checkpoint {
+    fn default() -> Self {
+        Self { radius: 2. }
+    }
+}
+
+// Position of the last checkpoint reached, saved with the game
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct LastCheckpoint(pub Option<Vec3>);
+
+// Where the player was when the game started, for levels without a `PlayerSpawn`
+#[derive(Resource, Default, Debug)]
+struct StartPosition(Option<Vec3>);
+
+// Local transform a body had when it was spawned, where it is recycled to
+#[derive(Component, Debug)]
+struct BodyHome(Transform);
+
+// B stands for blender
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct BBoundsExtra {
+    pub player_spawn: bool,
+    pub checkpoint: Option<BCheckpoint>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BCheckpoint {
+    pub radius: Option<f32>,
+}
+
+fn spawn_markers(
+    trigger: Trigger<ExtrasReady>,
+    mut commands: Commands,
+    children: Query<&Children>,
+    extras: Query<&Extras>,
+    reader: ExtrasReader,
+) {
+    let root = trigger.entity();
+    for entity in children.iter_descendants(root) {
+        let Some(node) = extras
+            .get(entity)
+            .ok()
+            .and_then(|extras| extras.node.as_ref())
+        else {
+            continue;
+        };
+        let Some(data) = reader.parse_value::<BBoundsExtra>(root, entity, node) else {
+            continue;
+        };
+        if data.player_spawn {
+            commands.entity(entity).insert(PlayerSpawn);
+        }
+        if let Some(checkpoint) = data.checkpoint {
+            commands.entity(entity).insert(Checkpoint {
+                radius: checkpoint.radius.unwrap_or(2.),
+            });
+        }
+    }
+}
+
+fn reset_respawn(mut last_checkpoint: ResMut<LastCheckpoint>, mut start: ResMut<StartPosition>) {
+    last_checkpoint.0 = None;
+    start.0 = None;
+}
+
+// The player isn't parented, its transform is already global before it is propagated
+fn record_start_position(
+    mut start: ResMut<StartPosition>,
+    player: Single<&Transform, With<Player>>,
+) {
+    if start.0.is_none() {
+        start.0 = Some(player.translation);
+    }
+}
+
+// The spawn comes with the level's scene, which is only ready a few frames after the player
+fn place_player(
+    mut commands: Commands,
+    spawns: Query<&GlobalTransform, Added<PlayerSpawn>>,
+    player: Single<
+        (Entity, &mut Transform, &mut LinearVelocity),
+        (With<Player>, With<AwaitingSpawn>),
+    >,
+) {
+    let Some(spawn) = spawns.iter().next() else {
+        return;
+    };
+    let (entity, mut transform, mut velocity) = player.into_inner();
+    transform.translation = spawn.translation();
+    velocity.0 = Vec3::ZERO;
+    commands.entity(entity).remove::<AwaitingSpawn>();
+}
+
+fn record_body_homes(
+    mut commands: Commands,
+    bodies: Query<(Entity, &Transform), (Added<RigidBody>, Without<Player>)>,
+) {
+    for (entity, transform) in &bodies {
+        commands.entity(entity).insert(BodyHome(*transform));
+    }
+}
+
+fn reach_checkpoints(
+    mut last_checkpoint: ResMut<LastCheckpoint>,
+    player: Single<&GlobalTransform, With<Player>>,
+    checkpoints: Query<(&Checkpoint, &GlobalTransform)>,
+) {
+    let player = player.translation();
+    for (checkpoint, transform) in &checkpoints {
+        let position = transform.translation();
+        if player.distance(position) <= checkpoint.radius && last_checkpoint.0 != Some(position) {
+            last_checkpoint.0 = Some(position);
+        }
+    }
+}
+
+fn respawn_player(
+    bounds: Res<WorldBounds>,
+    last_checkpoint: Res<LastCheckpoint>,
+    start: Res<StartPosition>,
+    spawns: Query<&GlobalTransform, With<PlayerSpawn>>,
+    player: Single<(&mut Transform, &mut LinearVelocity, &GlobalTransform), With<Player>>,
+) {
+    let (mut transform, mut velocity, global_transform) = player.into_inner();
+    if bounds.contains(global_transform.translation()) {
+        return;
+    }
+    let Some(point) = last_checkpoint
+        .0
+        .or_else(|| spawns.iter().next().map(GlobalTransform::translation))
+        .or(start.0)
+    else {
+        return;
+    };
+    transform.translation = point + Vec3::Y * bounds.respawn_height;
+    velocity.0 = Vec3::ZERO;
+}
+
+fn out_of_bounds_bodies(
+    mut commands: Commands,
+    bounds: Res<WorldBounds>,
+    mut bodies: Query<
+        (
+            Entity,
+            &RigidBody,
+            &GlobalTransform,
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+            Option<&BodyHome>,
+        ),
+        Without<Player>,
+    >,
+    parents: Query<&Parent>,
+    spawned: Query<(), With<Spawned>>,
+) {
+    for (entity, rigid_body, global_transform, mut linear_velocity, mut angular_velocity, home) in
+        &mut bodies
+    {
+        if !rigid_body.is_dynamic() || bounds.contains(global_transform.translation()) {
+            continue;
+        }
+        // Despawning only the body would leave its spawner counting an empty scene as alive
+        if let Some(root) = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| spawned.contains(*ancestor))
+        {
+            commands.entity(root).despawn_recursive();
+            continue;
+        }
+        match home {
+            Some(BodyHome(home)) if bounds.recycle_bodies => {
+                commands.entity(entity).insert(*home);
+                linear_velocity.0 = Vec3::ZERO;
+                angular_velocity.0 = Vec3::ZERO;
+            }
+            _ => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
@@ -1,203 +1,95 @@
-//! A freecam-style camera controller plugin.
-//! To use in your own application:
-//! - Copy the code for the [`CameraControllerPlugin`] and add the plugin to your App.
-//! - Attach the [`CameraController`] component to an entity with a [`Camera3d`].
-
-use bevy::{
-    input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
-    prelude::*,
-    window::CursorGrabMode,
-};
-use std::{f32::consts::*, fmt};
-
-pub struct CameraControllerPlugin;
-
-impl Plugin for CameraControllerPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_systems(Update, run_camera_controller);
-    }
-}
-
-/// Based on Valorant's default sensitivity, not entirely sure why it is exactly 1.0 / 180.0,
-/// but I'm guessing it is a misunderstanding between degrees/radians and then sticking with
-/// it because it felt nice.
-pub const RADIANS_PER_DOT: f32 = 1.0 / 180.0;
-
-#[derive(Component)]
+//! Looks through the player's eyes, the player's body is hidden while this mode is active.
+
+use crate::cameras::CameraMode;
+use crate::character::CharacterInput;
+use crate::input::{Action, ActionState};
+use crate::menu::MenuState;
+use crate::Player;
+use bevy::ecs::query::QueryFilter;
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+use std::f32::consts::*;
+use std::ops::Range;
+
+#[derive(Debug, Component)]
+#[require(Transform)]
 pub struct CameraController {
-    pub enabled: bool,
-    pub initialized: bool,
-    pub sensitivity: f32,
-    pub key_forward: KeyCode,
-    pub key_back: KeyCode,
-    pub key_left: KeyCode,
-    pub key_right: KeyCode,
-    pub key_up: KeyCode,
-    pub key_down: KeyCode,
-    pub key_run: KeyCode,
-    pub mouse_interact: MouseButton,
-    pub key_pause: KeyCode,
-    pub walk_speed: f32,
-    pub run_speed: f32,
-    pub scroll_factor: f32,
-    pub friction: f32,
-    pub pitch: f32,
-    pub yaw: f32,
-    pub velocity: Vec3,
+    // Height of the eyes above the center of the player
+    pub head_height: f32,
+    pub pitch_speed: f32,
+    // Clamp pitch to this range
+    pub pitch_range: Range<f32>,
+    pub yaw_speed: f32,
 }
 
 impl Default for CameraController {
     fn default() -> Self {
         Self {
-            enabled: false,
-            initialized: false,
-            sensitivity: 1.0,
-            key_forward: KeyCode::KeyW,
-            key_back: KeyCode::KeyS,
-            key_left: KeyCode::KeyA,
-            key_right: KeyCode::KeyD,
-            key_up: KeyCode::KeyE,
-            key_down: KeyCode::KeyQ,
-            key_run: KeyCode::ShiftLeft,
-            mouse_interact: MouseButton::Left,
-            key_pause: KeyCode::Escape,
-            walk_speed: 5.0,
-            run_speed: 15.0,
-            scroll_factor: 0.1,
-            friction: 0.5,
-            pitch: 0.0,
-            yaw: 0.0,
-            velocity: Vec3::ZERO,
+            head_height: 0.35,
+            // Looking straight up or down flips the yaw around
+            pitch_range: -(FRAC_PI_2 - 0.01)..(FRAC_PI_2 - 0.01),
+            pitch_speed: 0.003,
+            yaw_speed: 0.004,
         }
     }
 }
 
-impl fmt::Display for CameraController {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "
-Freecam Controls:
-    Mouse\t- Move camera orientation
-    Scroll\t- Adjust movement speed
-    {:?}\t- Hold to grab cursor
-    {:?}\t- Toggle cursor grab
-    {:?} & {:?}\t- Fly forward & backwards
-    {:?} & {:?}\t- Fly sideways left & right
-    {:?} & {:?}\t- Fly up & down
-    {:?}\t- Fly faster while held",
-            self.mouse_interact,
-            self.key_pause,
-            self.key_forward,
-            self.key_back,
-            self.key_left,
-            self.key_right,
-            self.key_up,
-            self.key_down,
-            self.key_run,
+pub struct CameraControllerPlugin;
+
+impl Plugin for CameraControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(CameraMode::FirstPerson),
+            hide_player::<With<Player>>,
         )
+        .add_systems(OnExit(CameraMode::FirstPerson), show_player)
+        .add_systems(
+            Update,
+            (
+                camera_controller_update.run_if(in_state(MenuState::Disabled)),
+                // A new game spawns a new player, the mode stays the same
+                hide_player::<Added<Player>>,
+            )
+                .run_if(in_state(CameraMode::FirstPerson)),
+        );
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-fn run_camera_controller(
-    time: Res<Time>,
-    mut window: Single<&mut Window>,
+fn camera_controller_update(
+    query: Single<(&mut Transform, &CameraController), (With<Camera>, Without<Player>)>,
     accumulated_mouse_motion: Res<AccumulatedMouseMotion>,
-    accumulated_mouse_scroll: Res<AccumulatedMouseScroll>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    key_input: Res<ButtonInput<KeyCode>>,
-    query: Single<(&mut Transform, &mut CameraController), With<Camera>>,
+    actions: Res<ActionState>,
+    player: Single<(&Transform, &mut CharacterInput), With<Player>>,
 ) {
-    let dt = time.delta_secs();
-
-    let (mut transform, mut controller) = query.into_inner();
-
-    if !controller.initialized {
-        let (yaw, pitch, _roll) = transform.rotation.to_euler(EulerRot::YXZ);
-        controller.yaw = yaw;
-        controller.pitch = pitch;
-        controller.enabled = true;
-        controller.initialized = true;
-        info!("{}", *controller);
-    }
-
-    if mouse_button_input.pressed(controller.mouse_interact) {
-        window.cursor_options.grab_mode = CursorGrabMode::Locked;
-        window.cursor_options.visible = false;
-        controller.enabled = true;
-    }
-
-    if key_input.pressed(controller.key_pause) {
-        window.cursor_options.grab_mode = CursorGrabMode::None;
-        window.cursor_options.visible = true;
-        controller.enabled = false;
-    }
-
-    if !controller.enabled {
-        return;
-    }
-
-    let mut scroll = 0.0;
-
-    let amount = match accumulated_mouse_scroll.unit {
-        MouseScrollUnit::Line => accumulated_mouse_scroll.delta.y,
-        MouseScrollUnit::Pixel => accumulated_mouse_scroll.delta.y / 16.0,
-    };
-    scroll += amount;
-    controller.walk_speed += scroll * controller.scroll_factor * controller.walk_speed;
-    controller.run_speed = controller.walk_speed * 3.0;
-
-    // Handle key input
-    let mut axis_input = Vec3::ZERO;
-    if key_input.pressed(controller.key_forward) {
-        axis_input.z += 1.0;
-    }
-    if key_input.pressed(controller.key_back) {
-        axis_input.z -= 1.0;
-    }
-    if key_input.pressed(controller.key_right) {
-        axis_input.x += 1.0;
-    }
-    if key_input.pressed(controller.key_left) {
-        axis_input.x -= 1.0;
-    }
-    if key_input.pressed(controller.key_up) {
-        axis_input.y += 1.0;
-    }
-    if key_input.pressed(controller.key_down) {
-        axis_input.y -= 1.0;
+    let (mut camera, controller) = query.into_inner();
+    let (player, mut character_input) = player.into_inner();
+
+    let delta = accumulated_mouse_motion.delta;
+    let (yaw, pitch, _) = camera.rotation.to_euler(EulerRot::YXZ);
+    let pitch = (pitch - delta.y * controller.pitch_speed + actions.look.y)
+        .clamp(controller.pitch_range.start, controller.pitch_range.end);
+    let yaw = yaw - delta.x * controller.yaw_speed - actions.look.x;
+    camera.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.);
+    camera.translation = player.translation + Vec3::Y * controller.head_height;
+
+    // Only the yaw matters for walking, looking down shouldn't slow the player down
+    let forward = Quat::from_rotation_y(yaw) * Vec3::NEG_Z;
+    let right = Quat::from_rotation_y(yaw) * Vec3::X;
+    character_input.direction = actions.movement.x * right + actions.movement.y * forward;
+    character_input.run = actions.pressed(Action::Run);
+    if actions.just_pressed(Action::Jump) {
+        character_input.jump = true;
     }
+}
 
-    // Apply movement update
-    if axis_input != Vec3::ZERO {
-        let max_speed = if key_input.pressed(controller.key_run) {
-            controller.run_speed
-        } else {
-            controller.walk_speed
-        };
-        controller.velocity = axis_input.normalize() * max_speed;
-    } else {
-        let friction = controller.friction.clamp(0.0, 1.0);
-        controller.velocity *= 1.0 - friction;
-        if controller.velocity.length_squared() < 1e-6 {
-            controller.velocity = Vec3::ZERO;
-        }
+fn hide_player<F: QueryFilter + 'static>(mut players: Query<&mut Visibility, F>) {
+    for mut visibility in &mut players {
+        *visibility = Visibility::Hidden;
     }
-    let forward = *transform.forward();
-    let right = *transform.right();
-    transform.translation += controller.velocity.x * dt * right
-        + controller.velocity.y * dt * Vec3::Y
-        + controller.velocity.z * dt * forward;
+}
 
-    // Handle mouse input
-    if accumulated_mouse_motion.delta != Vec2::ZERO {
-        // Apply look update
-        controller.pitch = (controller.pitch
-            - accumulated_mouse_motion.delta.y * RADIANS_PER_DOT * controller.sensitivity)
-            .clamp(-FRAC_PI_2, FRAC_PI_2);
-        controller.yaw -=
-            accumulated_mouse_motion.delta.x * RADIANS_PER_DOT * controller.sensitivity;
-        transform.rotation = Quat::from_euler(EulerRot::ZYX, 0.0, controller.yaw, controller.pitch);
+fn show_player(mut players: Query<&mut Visibility, With<Player>>) {
+    for mut visibility in &mut players {
+        *visibility = Visibility::Inherited;
     }
 }
@@ -0,0 +1,187 @@
+//! A freecam-style camera controller plugin, used as a debug spectator mode.
+//! To use in your own application:
+//! - Copy the code for the [`CameraControllerPlugin`] and add the plugin to your App.
+//! - Attach the [`CameraController`] component to an entity with a [`Camera3d`].
+
+use crate::cameras::CameraMode;
+use crate::input::{Action, ActionState, InputMap};
+use crate::menu::MenuState;
+use bevy::{
+    input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
+    prelude::*,
+    window::CursorGrabMode,
+};
+use std::f32::consts::*;
+
+pub struct CameraControllerPlugin;
+
+impl Plugin for CameraControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(CameraMode::Freecam), reset_camera_controller)
+            .add_systems(
+                Update,
+                run_camera_controller
+                    .run_if(in_state(MenuState::Disabled))
+                    .run_if(in_state(CameraMode::Freecam)),
+            );
+    }
+}
+
+/// Based on Valorant's default sensitivity, not entirely sure why it is exactly 1.0 / 180.0,
+/// but I'm guessing it is a misunderstanding between degrees/radians and then sticking with
+/// it because it felt nice.
+pub const RADIANS_PER_DOT: f32 = 1.0 / 180.0;
+
+#[derive(Component)]
+pub struct CameraController {
+    pub enabled: bool,
+    pub initialized: bool,
+    pub sensitivity: f32,
+    pub mouse_interact: MouseButton,
+    pub walk_speed: f32,
+    pub run_speed: f32,
+    pub scroll_factor: f32,
+    pub friction: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub velocity: Vec3,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            initialized: false,
+            sensitivity: 1.0,
+            mouse_interact: MouseButton::Left,
+            walk_speed: 5.0,
+            run_speed: 15.0,
+            scroll_factor: 0.1,
+            friction: 0.5,
+            pitch: 0.0,
+            yaw: 0.0,
+            velocity: Vec3::ZERO,
+        }
+    }
+}
+
+fn controls_help(controller: &CameraController, input_map: &InputMap) -> String {
+    format!(
+        "
+Freecam Controls:
+    Mouse\t- Move camera orientation
+    Scroll\t- Adjust movement speed
+    {:?}\t- Hold to grab cursor
+    {}\t- Release cursor
+    {} & {}\t- Fly forward & backwards
+    {} & {}\t- Fly sideways left & right
+    {} & {}\t- Fly up & down
+    {}\t- Fly faster while held",
+        controller.mouse_interact,
+        input_map.label(Action::Pause),
+        input_map.label(Action::MoveForward),
+        input_map.label(Action::MoveBack),
+        input_map.label(Action::MoveLeft),
+        input_map.label(Action::MoveRight),
+        input_map.label(Action::FlyUp),
+        input_map.label(Action::FlyDown),
+        input_map.label(Action::Run),
+    )
+}
+
+// Pick up the orientation the camera was left in by the other modes
+fn reset_camera_controller(mut controller: Single<&mut CameraController>) {
+    controller.initialized = false;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_camera_controller(
+    time: Res<Time>,
+    mut window: Single<&mut Window>,
+    accumulated_mouse_motion: Res<AccumulatedMouseMotion>,
+    accumulated_mouse_scroll: Res<AccumulatedMouseScroll>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    actions: Res<ActionState>,
+    input_map: Res<InputMap>,
+    query: Single<(&mut Transform, &mut CameraController), With<Camera>>,
+) {
+    let dt = time.delta_secs();
+
+    let (mut transform, mut controller) = query.into_inner();
+
+    if !controller.initialized {
+        let (yaw, pitch, _roll) = transform.rotation.to_euler(EulerRot::YXZ);
+        controller.yaw = yaw;
+        controller.pitch = pitch;
+        controller.enabled = true;
+        controller.initialized = true;
+        info!("{}", controls_help(&controller, &input_map));
+    }
+
+    if mouse_button_input.pressed(controller.mouse_interact) {
+        window.cursor_options.grab_mode = CursorGrabMode::Locked;
+        window.cursor_options.visible = false;
+        controller.enabled = true;
+    }
+
+    if actions.pressed(Action::Pause) {
+        window.cursor_options.grab_mode = CursorGrabMode::None;
+        window.cursor_options.visible = true;
+        controller.enabled = false;
+    }
+
+    if !controller.enabled {
+        return;
+    }
+
+    let mut scroll = 0.0;
+
+    let amount = match accumulated_mouse_scroll.unit {
+        MouseScrollUnit::Line => accumulated_mouse_scroll.delta.y,
+        MouseScrollUnit::Pixel => accumulated_mouse_scroll.delta.y / 16.0,
+    };
+    scroll += amount;
+    controller.walk_speed += scroll * controller.scroll_factor * controller.walk_speed;
+    controller.run_speed = controller.walk_speed * 3.0;
+
+    // Handle key input
+    let mut axis_input = Vec3::new(actions.movement.x, 0.0, actions.movement.y);
+    if actions.pressed(Action::FlyUp) {
+        axis_input.y += 1.0;
+    }
+    if actions.pressed(Action::FlyDown) {
+        axis_input.y -= 1.0;
+    }
+
+    // Apply movement update
+    if axis_input != Vec3::ZERO {
+        let max_speed = if actions.pressed(Action::Run) {
+            controller.run_speed
+        } else {
+            controller.walk_speed
+        };
+        controller.velocity = axis_input.normalize() * max_speed;
+    } else {
+        let friction = controller.friction.clamp(0.0, 1.0);
+        controller.velocity *= 1.0 - friction;
+        if controller.velocity.length_squared() < 1e-6 {
+            controller.velocity = Vec3::ZERO;
+        }
+    }
+    let forward = *transform.forward();
+    let right = *transform.right();
+    transform.translation += controller.velocity.x * dt * right
+        + controller.velocity.y * dt * Vec3::Y
+        + controller.velocity.z * dt * forward;
+
+    // Handle mouse input
+    if accumulated_mouse_motion.delta != Vec2::ZERO {
+        // Apply look update
+        controller.pitch = (controller.pitch
+            - accumulated_mouse_motion.delta.y * RADIANS_PER_DOT * controller.sensitivity)
+            .clamp(-FRAC_PI_2, FRAC_PI_2);
+        controller.yaw -=
+            accumulated_mouse_motion.delta.x * RADIANS_PER_DOT * controller.sensitivity;
+        transform.rotation = Quat::from_euler(EulerRot::ZYX, 0.0, controller.yaw, controller.pitch);
+    }
+}
@@ -1,6 +1,10 @@
+use crate::character::CharacterInput;
+use crate::input::{Action, ActionState};
+use crate::menu::MenuState;
 use bevy::prelude::*;
 
 pub mod first_person;
+pub mod freecam;
 pub mod third_person;
 
 #[derive(Component)]
@@ -8,3 +12,49 @@ pub struct OnCameraUIReticle;
 
 #[derive(Component)]
 pub struct OnCameraUIInteract;
+
+// Which of the controllers attached to the camera is driving it
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+pub enum CameraMode {
+    #[default]
+    ThirdPerson,
+    FirstPerson,
+    // Debug spectator camera, detached from the player
+    Freecam,
+}
+
+// Adds every camera controller, the camera entity needs all of their `CameraController` components
+pub fn cameras_plugin(app: &mut App) {
+    app.init_state::<CameraMode>()
+        .add_plugins((
+            third_person::CameraControllerPlugin,
+            first_person::CameraControllerPlugin,
+            freecam::CameraControllerPlugin,
+        ))
+        .add_systems(OnEnter(CameraMode::Freecam), stop_player)
+        .add_systems(
+            Update,
+            cycle_camera_mode.run_if(in_state(MenuState::Disabled)),
+        );
+}
+
+fn cycle_camera_mode(
+    actions: Res<ActionState>,
+    camera_mode: Res<State<CameraMode>>,
+    mut next_camera_mode: ResMut<NextState<CameraMode>>,
+) {
+    if actions.just_pressed(Action::CycleCamera) {
+        next_camera_mode.set(match camera_mode.get() {
+            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+            CameraMode::FirstPerson => CameraMode::Freecam,
+            CameraMode::Freecam => CameraMode::ThirdPerson,
+        });
+    }
+}
+
+// The player would otherwise keep walking in the direction it was going
+fn stop_player(mut characters: Query<&mut CharacterInput>) {
+    for mut input in &mut characters {
+        *input = CharacterInput::default();
+    }
+}
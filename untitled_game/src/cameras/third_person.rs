@@ -1,9 +1,12 @@
-use crate::cameras::{OnCameraUIInteract, OnCameraUIReticle};
+use crate::cameras::{CameraMode, OnCameraUIReticle};
+use crate::character::CharacterInput;
+use crate::input::{Action, ActionState};
 use crate::menu::MenuState;
-use crate::{despawn_screen, Player, PlayerState};
+use crate::triggers::in_volume;
+use crate::{despawn_screen, Player};
 use crate::{pause_physics, unpause_physics};
 use avian3d::prelude::*;
-use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
 use bevy::prelude::*;
 use bevy::window::CursorGrabMode;
 use std::f32::consts::*;
@@ -12,103 +15,90 @@ use std::ops::Range;
 #[derive(Debug, Component)]
 #[require(Transform)]
 pub struct CameraController {
+    // Distance the camera wants to be at, changed by zooming
     pub orbit_distance: f32,
+    // Clamp zoom to this range
+    pub distance_range: Range<f32>,
+    // Distance zoomed per scroll wheel line
+    pub zoom_speed: f32,
+    // The camera orbits around this point relative to the player
+    pub target_offset: Vec3,
+    // Sideways offset of the orbit point, negative is over the left shoulder
+    pub shoulder_offset: f32,
+    // Radius of the sphere cast toward the camera, keeps the near plane out of walls
+    pub collision_radius: f32,
+    // Decay rates of the damping, higher is snappier and they don't depend on the frame rate
+    pub follow_decay: f32,
+    pub rotation_decay: f32,
+    pub zoom_decay: f32,
     pub pitch_speed: f32,
     // Clamp pitch to this range
     pub pitch_range: Range<f32>,
     pub yaw_speed: f32,
-    pub key_left: KeyCode,
-    pub key_right: KeyCode,
-    pub key_up: KeyCode,
-    pub key_down: KeyCode,
-    pub key_pause: KeyCode,
-    pub key_interact: KeyCode,
-    pub mouse_interact: MouseButton,
-    pub walk_speed: f32,
-    pub run_speed: f32,
-    pub friction: f32,
-    pub velocity: Vec3,
-    pub key_run: KeyCode,
+    // Damped state, picked up from the camera transform when the mode is entered
+    pub initialized: bool,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub target: Vec3,
+    // Distance after collisions, the camera is pulled in immediately but eases back out
+    pub current_distance: f32,
 }
 
 impl Default for CameraController {
     fn default() -> Self {
         Self {
-            friction: 0.1,
-            key_down: KeyCode::KeyS,
-            key_left: KeyCode::KeyA,
-            key_pause: KeyCode::Escape,
-            key_right: KeyCode::KeyD,
-            key_up: KeyCode::KeyW,
-            key_run: KeyCode::ShiftLeft,
-            key_interact: KeyCode::KeyE,
-            mouse_interact: MouseButton::Left,
-            orbit_distance: 10.0,
+            orbit_distance: 6.0,
+            distance_range: 2.0..15.0,
+            zoom_speed: 1.0,
+            target_offset: Vec3::new(0., 1., 0.),
+            shoulder_offset: 0.6,
+            collision_radius: 0.2,
+            follow_decay: 12.0,
+            rotation_decay: 20.0,
+            zoom_decay: 4.0,
             // Limiting pitch stops some unexpected rotation
             pitch_range: -(FRAC_PI_2 - FRAC_1_PI)..FRAC_1_PI,
             pitch_speed: 0.003,
-            run_speed: 15.0,
-            walk_speed: 5.0,
             yaw_speed: 0.004,
-            velocity: Vec3::ZERO,
+            initialized: false,
+            yaw: 0.,
+            pitch: 0.,
+            target: Vec3::ZERO,
+            current_distance: 6.0,
         }
     }
 }
 
+// Overrides the camera's distance while the player is in its volume, see `triggers`
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct CameraHint {
+    pub distance: f32,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn camera_controller_update(
+    time: Res<Time>,
     query: Single<(&mut Transform, &mut CameraController), (With<Camera>, Without<Player>)>,
     accumulated_mouse_motion: Res<AccumulatedMouseMotion>,
-    time: Res<Time>,
-    key_input: Res<ButtonInput<KeyCode>>,
-    mut window: Single<&mut Window>,
-    mut player: Single<&mut Transform, With<Player>>,
-    mut menu_state: ResMut<NextState<MenuState>>,
+    accumulated_mouse_scroll: Res<AccumulatedMouseScroll>,
+    actions: Res<ActionState>,
+    player: Single<(Entity, &Transform, &mut CharacterInput), With<Player>>,
+    hints: Query<(&CameraHint, &GlobalTransform)>,
+    spatial_query: SpatialQuery,
 ) {
-    let (mut camera, mut controller) = query.into_inner();
-
-    if key_input.pressed(controller.key_pause) {
-        window.cursor_options.grab_mode = CursorGrabMode::None;
-        window.cursor_options.visible = true;
-        menu_state.set(MenuState::Main)
-    }
-
     let dt = time.delta_secs();
+    let (mut camera, mut controller) = query.into_inner();
+    let (player_entity, player, mut character_input) = player.into_inner();
 
-    let mut axis_input = Vec3::ZERO;
-    if key_input.pressed(controller.key_up) {
-        axis_input.z += 1.0;
-    }
-    if key_input.pressed(controller.key_down) {
-        axis_input.z -= 1.0;
-    }
-    if key_input.pressed(controller.key_right) {
-        axis_input.x += 1.0;
-    }
-    if key_input.pressed(controller.key_left) {
-        axis_input.x -= 1.0;
-    }
-
-    // Apply movement update
-    if axis_input != Vec3::ZERO {
-        let max_speed = if key_input.pressed(controller.key_run) {
-            controller.run_speed
-        } else {
-            controller.walk_speed
-        };
-        controller.velocity = axis_input.normalize() * max_speed;
-    } else {
-        let friction = controller.friction.clamp(0.0, 1.0);
-        controller.velocity *= 1.0 - friction;
-        if controller.velocity.length_squared() < 1e-6 {
-            controller.velocity = Vec3::ZERO;
-        }
+    if !controller.initialized {
+        let (yaw, pitch, _roll) = camera.rotation.to_euler(EulerRot::YXZ);
+        controller.yaw = yaw;
+        controller.pitch = pitch.clamp(controller.pitch_range.start, controller.pitch_range.end);
+        controller.target = player.translation + controller.target_offset;
+        controller.current_distance = camera.translation.distance(controller.target);
+        controller.initialized = true;
     }
-    let forward = camera.forward();
-    let forward = Dir3::new(Vec3::new(forward.x, 0., forward.z)).unwrap();
-    let right = camera.right();
-    player.translation += controller.velocity.x * dt * right
-        + controller.velocity.y * dt * Vec3::Y
-        + controller.velocity.z * dt * forward;
 
     let delta = accumulated_mouse_motion.delta;
 
@@ -118,20 +108,122 @@ fn camera_controller_update(
     let delta_pitch = delta.y * controller.pitch_speed;
     let delta_yaw = delta.x * controller.yaw_speed;
 
-    // Obtain the existing pitch, yaw, and roll values from the transform.
-    let (yaw, pitch, roll) = camera.rotation.to_euler(EulerRot::YXZ);
-
     // Establish the new yaw and pitch, preventing the pitch value from exceeding our limits.
-    let pitch =
-        (pitch + delta_pitch).clamp(controller.pitch_range.start, controller.pitch_range.end);
-    let yaw = yaw - delta_yaw;
-    camera.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+    // The stick is already scaled by the frame time, it moves the camera like the mouse does
+    controller.pitch = (controller.pitch + delta_pitch - actions.look.y)
+        .clamp(controller.pitch_range.start, controller.pitch_range.end);
+    controller.yaw -= delta_yaw + actions.look.x;
+    let rotation = Quat::from_euler(EulerRot::YXZ, controller.yaw, controller.pitch, 0.);
+
+    // Movement follows where the player aims rather than the damped camera
+    let forward = Quat::from_rotation_y(controller.yaw) * Vec3::NEG_Z;
+    let right = Quat::from_rotation_y(controller.yaw) * Vec3::X;
+    // Not normalized so a partly tilted stick walks slower
+    character_input.direction = actions.movement.x * right + actions.movement.y * forward;
+    character_input.run = actions.pressed(Action::Run);
+    if actions.just_pressed(Action::Jump) {
+        character_input.jump = true;
+    }
 
-    let target = player.translation + Vec3::new(0., 1., 0.1);
+    let scroll = match accumulated_mouse_scroll.unit {
+        MouseScrollUnit::Line => accumulated_mouse_scroll.delta.y,
+        MouseScrollUnit::Pixel => accumulated_mouse_scroll.delta.y / 16.0,
+    };
+    controller.orbit_distance = (controller.orbit_distance - scroll * controller.zoom_speed).clamp(
+        controller.distance_range.start,
+        controller.distance_range.end,
+    );
+
+    if actions.just_pressed(Action::SwapShoulder) {
+        controller.shoulder_offset = -controller.shoulder_offset;
+    }
+
+    let decay = controller.rotation_decay;
+    camera.rotation.smooth_nudge(&rotation, decay, dt);
+
+    let decay = controller.follow_decay;
+    let target = player.translation + controller.target_offset;
+    controller.target.smooth_nudge(&target, decay, dt);
+
+    // Sweep a sphere from the player to the shoulder, then toward where the camera wants to be,
+    // anything in between would hide the player or put the camera inside a wall
+    let filter = SpatialQueryFilter::default().with_excluded_entities([player_entity]);
+    let shoulder =
+        Dir3::new(camera.right() * controller.shoulder_offset).map_or(Vec3::ZERO, |side| {
+            side * cast_distance(
+                &spatial_query,
+                controller.collision_radius,
+                controller.target,
+                side,
+                controller.shoulder_offset.abs(),
+                &filter,
+            )
+        });
+    let pivot = controller.target + shoulder;
+    // The player isn't parented, its translation is already global
+    let orbit_distance = hints
+        .iter()
+        .find(|(_, transform)| in_volume(transform, player.translation))
+        .map_or(controller.orbit_distance, |(hint, _)| hint.distance);
+    let allowed_distance = cast_distance(
+        &spatial_query,
+        controller.collision_radius,
+        pivot,
+        -camera.forward(),
+        orbit_distance,
+        &filter,
+    );
+
+    if allowed_distance < controller.current_distance {
+        controller.current_distance = allowed_distance;
+    } else {
+        let decay = controller.zoom_decay;
+        controller
+            .current_distance
+            .smooth_nudge(&allowed_distance, decay, dt);
+    }
 
     // Adjust the translation to maintain the correct orientation toward the orbit target.
-    // In our example it's a static target, but this could easily be customized.
-    camera.translation = target - camera.forward() * controller.orbit_distance;
+    camera.translation = pivot - camera.forward() * controller.current_distance;
+}
+
+// How far a sphere can travel before hitting something, up to `max_distance`
+fn cast_distance(
+    spatial_query: &SpatialQuery,
+    radius: f32,
+    origin: Vec3,
+    direction: Dir3,
+    max_distance: f32,
+    filter: &SpatialQueryFilter,
+) -> f32 {
+    spatial_query
+        .cast_shape(
+            &Collider::sphere(radius),
+            origin,
+            Quat::IDENTITY,
+            direction,
+            &ShapeCastConfig::from_max_distance(max_distance),
+            filter,
+        )
+        .map_or(max_distance, |hit| hit.distance)
+}
+
+// Pick up the orientation the camera was left in by the other modes
+fn reset_camera_controller(mut controller: Single<&mut CameraController>) {
+    controller.initialized = false;
+}
+
+// Opens the menu in every camera mode
+fn pause_game(
+    actions: Res<ActionState>,
+    mut window: Single<&mut Window>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        window.cursor_options.grab_mode = CursorGrabMode::None;
+        window.cursor_options.visible = true;
+        menu_state.set(MenuState::Main)
+    }
 }
 
 fn camera_controller_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -151,6 +243,7 @@ fn camera_controller_setup(mut commands: Commands, asset_server: Res<AssetServer
 
 pub struct CameraControllerPlugin;
 
+// Whether the interaction prompt is shown, see `interaction`
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum CameraUIState {
     Visible,
@@ -161,6 +254,8 @@ pub enum CameraUIState {
 impl Plugin for CameraControllerPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<CameraUIState>()
+            .register_type::<CameraHint>()
+            .add_systems(OnEnter(CameraMode::ThirdPerson), reset_camera_controller)
             .add_systems(
                 OnEnter(MenuState::Disabled),
                 (camera_controller_setup, unpause_physics),
@@ -169,77 +264,17 @@ impl Plugin for CameraControllerPlugin {
                 OnExit(MenuState::Disabled),
                 (despawn_screen::<OnCameraUIReticle>, pause_physics, hide_ui),
             )
-            .add_systems(OnEnter(CameraUIState::Visible), interaction_display_setup)
-            .add_systems(
-                OnExit(CameraUIState::Visible),
-                despawn_screen::<OnCameraUIInteract>,
-            )
             .add_systems(
                 Update,
-                (camera_controller_update, print_hits).run_if(in_state(MenuState::Disabled)),
+                (
+                    pause_game,
+                    camera_controller_update.run_if(in_state(CameraMode::ThirdPerson)),
+                )
+                    .run_if(in_state(MenuState::Disabled)),
             );
     }
 }
 
-fn print_hits(
-    camera: Single<(&mut Transform, &CameraController), With<Camera3d>>,
-    key_input: Res<ButtonInput<KeyCode>>,
-    mut camera_ui_state: ResMut<NextState<CameraUIState>>,
-    spatial_query: SpatialQuery,
-    mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    player_state: Res<State<PlayerState>>,
-) {
-    let (camera_transform, camera_controller) = camera.into_inner();
-    // Ray origin and direction
-    let origin = camera_transform.translation;
-    let direction = camera_transform.forward();
-
-    // Configuration for the ray cast
-    let max_distance = 100.0;
-    let solid = true;
-    let filter = SpatialQueryFilter::default();
-
-    // Cast ray and print first hit
-    if let Some(first_hit) = spatial_query.cast_ray(origin, direction, max_distance, solid, &filter)
-    {
-        if let PlayerState::Id(player) = player_state.clone() {
-            if first_hit.entity != player {
-                camera_ui_state.set(CameraUIState::Visible);
-                if key_input.just_pressed(camera_controller.key_interact) {
-                    commands
-                        .entity(first_hit.entity)
-                        .insert(MeshMaterial3d(materials.add(Color::srgb_u8(0, 0, 0))));
-                }
-            } else {
-                camera_ui_state.set(CameraUIState::Hidden);
-            }
-        } else {
-            camera_ui_state.set(CameraUIState::Hidden);
-        }
-    } else {
-        camera_ui_state.set(CameraUIState::Hidden);
-    }
-}
-
-fn interaction_display_setup(mut commands: Commands, camera_controller: Single<&CameraController>) {
-    commands
-        .spawn((
-            Node {
-                width: Val::Percent(100.),
-                height: Val::Percent(100.),
-                align_items: AlignItems::End,
-                justify_content: JustifyContent::End,
-                ..default()
-            },
-            OnCameraUIInteract,
-        ))
-        .with_child(Text::new(format!(
-            "Press {:?} to Interact",
-            camera_controller.key_interact
-        )));
-}
-
 fn hide_ui(mut camera_ui_state: ResMut<NextState<CameraUIState>>) {
     camera_ui_state.set(CameraUIState::Hidden);
 }
@@ -0,0 +1,207 @@
+//! A dynamic rigid body character controller.
+//! Spawn the player with a [`CharacterControllerBundle`] and write the desired movement into its
+//! [`CharacterInput`] every frame, physics takes care of gravity and collisions.
+
+use crate::menu::MenuState;
+use crate::GameLayer;
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use std::f32::consts::*;
+
+pub fn character_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (update_grounded, step_up, movement)
+            .chain()
+            .run_if(in_state(MenuState::Disabled)),
+    );
+}
+
+#[derive(Component, Debug)]
+pub struct CharacterController {
+    pub walk_speed: f32,
+    pub run_speed: f32,
+    // How fast the horizontal velocity reaches the desired speed, in units per second squared
+    pub acceleration: f32,
+    // Fraction of the acceleration available while not grounded
+    pub air_control: f32,
+    pub jump_speed: f32,
+    // Steeper ground doesn't count as ground, the character slides down instead
+    pub max_slope_angle: f32,
+    // Ledges up to this height are climbed automatically while walking into them
+    pub max_step_height: f32,
+    // Half of the distance between the top and bottom of the collider
+    pub half_height: f32,
+    pub radius: f32,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            walk_speed: 5.0,
+            run_speed: 15.0,
+            acceleration: 60.0,
+            air_control: 0.2,
+            jump_speed: 6.0,
+            max_slope_angle: FRAC_PI_4,
+            max_step_height: 0.3,
+            half_height: 0.5,
+            radius: 0.3,
+        }
+    }
+}
+
+// What the character wants to do this frame, written by the camera controllers
+#[derive(Component, Debug, Default)]
+pub struct CharacterInput {
+    // Horizontal world space direction, with a length of at most 1
+    pub direction: Vec3,
+    pub run: bool,
+    pub jump: bool,
+}
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Grounded;
+
+#[derive(Bundle)]
+pub struct CharacterControllerBundle {
+    controller: CharacterController,
+    input: CharacterInput,
+    rigid_body: RigidBody,
+    collider: Collider,
+    ground_caster: ShapeCaster,
+    locked_axes: LockedAxes,
+    friction: Friction,
+    collision_layers: CollisionLayers,
+}
+
+impl CharacterControllerBundle {
+    // The collider is a capsule so the character slides over small bumps instead of catching on them
+    pub fn new(radius: f32, height: f32) -> Self {
+        let collider = Collider::capsule(radius, height - radius * 2.);
+        // Slightly smaller than the collider so it doesn't hit walls the character is touching
+        let mut caster_shape = collider.clone();
+        caster_shape.set_scale(Vec3::ONE * 0.99, 10);
+
+        Self {
+            controller: CharacterController {
+                half_height: height / 2.,
+                radius,
+                ..default()
+            },
+            input: CharacterInput::default(),
+            rigid_body: RigidBody::Dynamic,
+            collider,
+            ground_caster: ShapeCaster::new(caster_shape, Vec3::ZERO, Quat::IDENTITY, Dir3::NEG_Y)
+                .with_max_distance(0.2),
+            locked_axes: LockedAxes::ROTATION_LOCKED,
+            // Otherwise the character sticks to walls it is pushed against
+            friction: Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
+            // Lets bodies leave out the player, e.g. while they are carried
+            collision_layers: CollisionLayers::new(GameLayer::Player, LayerMask::ALL),
+        }
+    }
+}
+
+fn update_grounded(
+    mut commands: Commands,
+    query: Query<(Entity, &ShapeHits, &Rotation, &CharacterController)>,
+) {
+    for (entity, hits, rotation, controller) in &query {
+        let is_grounded = hits.iter().any(|hit| {
+            (rotation * -hit.normal2).angle_between(Vec3::Y).abs() <= controller.max_slope_angle
+        });
+
+        if is_grounded {
+            commands.entity(entity).insert(Grounded);
+        } else {
+            commands.entity(entity).remove::<Grounded>();
+        }
+    }
+}
+
+// Lift the character on top of low obstacles it is walking into
+fn step_up(
+    mut query: Query<
+        (Entity, &mut Position, &CharacterInput, &CharacterController),
+        With<Grounded>,
+    >,
+    spatial_query: SpatialQuery,
+) {
+    for (entity, mut position, input, controller) in &mut query {
+        let Ok(direction) = Dir3::new(input.direction) else {
+            continue;
+        };
+        let filter = SpatialQueryFilter::default().with_excluded_entities([entity]);
+        let feet = position.0 - Vec3::Y * (controller.half_height - 0.05);
+        let reach = controller.radius + 0.1;
+
+        // Nothing in front of the feet, or too high to step on
+        if spatial_query
+            .cast_ray(feet, direction, reach, true, &filter)
+            .is_none()
+        {
+            continue;
+        }
+        let above = feet + Vec3::Y * controller.max_step_height;
+        if spatial_query
+            .cast_ray(above, direction, reach, true, &filter)
+            .is_some()
+        {
+            continue;
+        }
+
+        // Find the top of the step right in front of the character
+        let ahead = above + direction * reach;
+        if let Some(hit) = spatial_query.cast_ray(
+            ahead,
+            Dir3::NEG_Y,
+            controller.max_step_height,
+            true,
+            &filter,
+        ) {
+            let step = controller.max_step_height - hit.distance;
+            if step > 0.01 {
+                position.y += step + 0.01;
+            }
+        }
+    }
+}
+
+fn movement(
+    time: Res<Time>,
+    mut query: Query<(
+        &mut LinearVelocity,
+        &mut CharacterInput,
+        &CharacterController,
+        Has<Grounded>,
+    )>,
+) {
+    let dt = time.delta_secs();
+
+    for (mut velocity, mut input, controller, is_grounded) in &mut query {
+        let speed = if input.run {
+            controller.run_speed
+        } else {
+            controller.walk_speed
+        };
+        let acceleration = if is_grounded {
+            controller.acceleration
+        } else {
+            controller.acceleration * controller.air_control
+        };
+
+        let direction = input.direction.clamp_length_max(1.);
+        let target = Vec2::new(direction.x, direction.z) * speed;
+        let horizontal = Vec2::new(velocity.x, velocity.z).move_towards(target, acceleration * dt);
+        velocity.x = horizontal.x;
+        velocity.z = horizontal.y;
+
+        if input.jump && is_grounded {
+            velocity.y = controller.jump_speed;
+        }
+        // Jumping is only requested for the frame the key was pressed
+        input.jump = false;
+    }
+}
@@ -1,5 +1,5 @@
 // Components, Resources & States
-use avian3d::prelude::RigidBody;
+use avian3d::prelude::{PhysicsLayer, RigidBody};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +9,8 @@ pub struct Cubemap {
     pub image_handle: Handle<Image>,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 #[require(Transform)]
 pub struct Player;
 
@@ -27,25 +28,61 @@ pub enum GameState {
     Game,
 }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(
+    Resource, Reflect, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
 pub enum DisplayQuality {
     Low,
     Medium,
     High,
 }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+// Master volume from 0 to 9, scales the music and sound effect volumes
+#[derive(
+    Resource, Reflect, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
 pub struct Volume(pub u32);
 
+// Volume of the background music from 0 to 9
+#[derive(
+    Resource, Reflect, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub struct MusicVolume(pub u32);
+
+// Volume of the sound effects, including the menu clicks, from 0 to 9
+#[derive(
+    Resource, Reflect, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub struct SfxVolume(pub u32);
+
 // B stands for blender
+// Lengths are the object's dimensions as shown in Blender, before the y and z axes are swapped
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct BMeshExtra {
     pub collider: BCollider,
     pub rigid_body: BRigidBody,
     pub cube_size: Option<Vec3>,
     pub sphere_radius: Option<f32>,
+    // Of capsules and cylinders, the height is along Blender's z axis and includes the caps
+    pub radius: Option<f32>,
+    pub height: Option<f32>,
+    // Mass overrides the density, which is otherwise 1 like in Blender
+    pub mass: Option<f32>,
+    pub density: Option<f32>,
+    pub friction: Option<f32>,
+    pub restitution: Option<f32>,
+    // Sensors detect collisions without blocking anything
+    #[serde(default)]
+    pub sensor: bool,
+    // Layers the collider is on, and the layers it collides with, all of them by default
+    pub layers: Option<Vec<GameLayer>>,
+    pub collides_with: Option<Vec<GameLayer>>,
 }
 
 // B stands for blender
@@ -53,12 +90,20 @@ pub struct BMeshExtra {
 pub enum BCollider {
     TrimeshFromMesh,
     Cuboid,
+    Sphere,
+    Capsule,
+    Cylinder,
+    ConvexHull,
+    // Several convex hulls, for concave dynamic bodies which can't use a trimesh
+    ConvexDecomposition,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum BRigidBody {
     Static,
     Dynamic,
+    // Moved by code or animations, pushes dynamic bodies without being pushed back
+    Kinematic,
 }
 
 impl From<BRigidBody> for RigidBody {
@@ -66,6 +111,18 @@ impl From<BRigidBody> for RigidBody {
         match value {
             BRigidBody::Dynamic => RigidBody::Dynamic,
             BRigidBody::Static => RigidBody::Static,
+            BRigidBody::Kinematic => RigidBody::Kinematic,
         }
     }
 }
+
+// Collision layers that can be set from Blender
+#[derive(PhysicsLayer, Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    Level,
+    Props,
+    Player,
+    Sensors,
+}
@@ -0,0 +1,292 @@
+//! Reading the custom properties Blender exports as glTF extras. They are parsed once when their
+//! scene is ready and kept in [`Extras`], then [`ExtrasReady`] is triggered on the scene root for
+//! the feature modules to read them. Malformed properties are logged with the node and scene they
+//! are on instead of being skipped, and fail loudly in strict mode.
+//! Properties named after a registered component insert that component, so level logic can be
+//! built from Blender without a dedicated struct for each feature.
+
+use bevy::ecs::reflect::ReflectCommandExt;
+use bevy::ecs::system::SystemParam;
+use bevy::gltf::{GltfExtras, GltfMaterialExtras, GltfMeshExtras, GltfSceneExtras};
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::{ReflectFromReflect, TypeRegistration, TypeRegistry};
+use bevy::scene::SceneInstanceReady;
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use std::fmt::Display;
+
+pub fn extras_plugin(app: &mut App) {
+    app.init_resource::<ExtrasConfig>()
+        .add_observer(parse_extras)
+        .add_observer(insert_reflected_components);
+}
+
+// The custom properties of a node, each one a JSON object
+#[derive(Component, Debug)]
+pub struct Extras {
+    pub node: Option<serde_json::Value>,
+    // The mesh and material extras of a primitive are on the same entity
+    pub mesh: Option<serde_json::Value>,
+    pub scene: Option<serde_json::Value>,
+    pub material: Option<serde_json::Value>,
+}
+
+// Every snake_case property some module reads from the node extras, any other one is reported as a
+// typo. A module reading a new property adds it here
+const NODE_PROPERTIES: &[&str] = &[
+    // interaction
+    "interactable",
+    "door",
+    "switch",
+    "pickup",
+    // bounds
+    "player_spawn",
+    "checkpoint",
+    // triggers
+    "trigger",
+    "camera_hint",
+    // spawner
+    "spawner",
+];
+
+// Triggered on a scene root once the extras of all its nodes are parsed
+#[derive(Event, Debug)]
+pub struct ExtrasReady;
+
+#[derive(Resource, Debug)]
+pub struct ExtrasConfig {
+    // Panics on malformed extras, so a level with a typo can't be played by accident
+    pub strict: bool,
+    // Logs the components each node got from its extras
+    pub log_summary: bool,
+}
+
+// Both can be turned on without rebuilding, with the `STRICT_EXTRAS` and `EXTRAS_SUMMARY`
+// environment variables
+impl Default for ExtrasConfig {
+    fn default() -> Self {
+        Self {
+            strict: std::env::var_os("STRICT_EXTRAS").is_some(),
+            log_summary: std::env::var_os("EXTRAS_SUMMARY").is_some(),
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct ExtrasReader<'w, 's> {
+    config: Res<'w, ExtrasConfig>,
+    names: Query<'w, 's, &'static Name>,
+    scene_roots: Query<'w, 's, &'static SceneRoot>,
+}
+
+impl ExtrasReader<'_, '_> {
+    // The entity is the node the extras are on and the root is the scene instance it is part of
+    pub fn parse<T: DeserializeOwned>(
+        &self,
+        root: Entity,
+        entity: Entity,
+        json: &str,
+    ) -> Option<T> {
+        match serde_json::from_str(json) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                self.report(root, entity, err, json);
+                None
+            }
+        }
+    }
+
+    // For extras that were already parsed, e.g. the ones kept in `Extras`
+    pub fn parse_value<T: DeserializeOwned>(
+        &self,
+        root: Entity,
+        entity: Entity,
+        value: &serde_json::Value,
+    ) -> Option<T> {
+        match T::deserialize(value) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                self.report(root, entity, err, value);
+                None
+            }
+        }
+    }
+
+    // Node properties are read by several modules into lenient structs, so a misspelled one is only
+    // caught here
+    pub fn report_unknown_properties(
+        &self,
+        root: Entity,
+        entity: Entity,
+        node: &serde_json::Value,
+    ) {
+        let Some(properties) = node.as_object() else {
+            return;
+        };
+        for key in properties.keys() {
+            if !is_component_key(key) && !NODE_PROPERTIES.contains(&key.as_str()) {
+                self.report(root, entity, format!("unknown property {key}"), node);
+            }
+        }
+    }
+
+    // For extras that are valid JSON but can't be used, e.g. an unknown component name
+    pub fn report(&self, root: Entity, entity: Entity, err: impl Display, json: impl Display) {
+        let message = format!(
+            "Malformed extras on {} in {}: {err}\n{json}",
+            self.node_name(entity),
+            self.scene_path(root)
+        );
+        if self.config.strict {
+            panic!("{message}");
+        }
+        error!("{message}");
+    }
+
+    // Lists what the node got from its extras, e.g. ["Dynamic body", "Cuboid collider"]
+    pub fn summary(&self, root: Entity, entity: Entity, components: &[String]) {
+        if self.config.log_summary {
+            info!(
+                "{} in {}: {}",
+                self.node_name(entity),
+                self.scene_path(root),
+                components.join(", ")
+            );
+        }
+    }
+
+    fn node_name(&self, entity: Entity) -> String {
+        self.names.get(entity).map_or_else(
+            |_| format!("unnamed node {entity}"),
+            |name| format!("\"{name}\""),
+        )
+    }
+
+    fn scene_path(&self, root: Entity) -> String {
+        self.scene_roots
+            .get(root)
+            .ok()
+            .and_then(|scene_root| scene_root.0.path())
+            .map_or_else(|| String::from("an unsaved scene"), ToString::to_string)
+    }
+}
+
+fn parse_extras(
+    trigger: Trigger<SceneInstanceReady>,
+    mut commands: Commands,
+    children: Query<&Children>,
+    extras: Query<(
+        Option<&GltfExtras>,
+        Option<&GltfMeshExtras>,
+        Option<&GltfSceneExtras>,
+        Option<&GltfMaterialExtras>,
+    )>,
+    reader: ExtrasReader,
+) {
+    let root = trigger.entity();
+    for entity in children.iter_descendants(root) {
+        let Ok((node, mesh, scene, material)) = extras.get(entity) else {
+            continue;
+        };
+        let parse = |json: Option<&String>| {
+            let value = reader.parse::<serde_json::Value>(root, entity, json?)?;
+            if !value.is_object() {
+                reader.report(root, entity, "extras must be an object", &value);
+                return None;
+            }
+            Some(value)
+        };
+        let parsed = Extras {
+            node: parse(node.map(|extras| &extras.value)),
+            mesh: parse(mesh.map(|extras| &extras.value)),
+            scene: parse(scene.map(|extras| &extras.value)),
+            material: parse(material.map(|extras| &extras.value)),
+        };
+        if let Some(node) = &parsed.node {
+            reader.report_unknown_properties(root, entity, node);
+        }
+        if parsed.node.is_some()
+            || parsed.mesh.is_some()
+            || parsed.scene.is_some()
+            || parsed.material.is_some()
+        {
+            commands.entity(entity).insert(parsed);
+        }
+    }
+    // Commands are applied in order, so the observers see the inserted extras
+    commands.trigger_targets(ExtrasReady, root);
+}
+
+// Custom properties named after a registered component insert it on the entity they are on, e.g.
+// `{"Interactable": {"prompt": "Read"}}`. Fields that are left out keep their default value when
+// the component reflects `Default`
+fn insert_reflected_components(
+    trigger: Trigger<ExtrasReady>,
+    mut commands: Commands,
+    children: Query<&Children>,
+    extras: Query<&Extras>,
+    registry: Res<AppTypeRegistry>,
+    reader: ExtrasReader,
+) {
+    let root = trigger.entity();
+    let registry = registry.read();
+    for entity in children.iter_descendants(root) {
+        let Ok(extras) = extras.get(entity) else {
+            continue;
+        };
+        let values = [&extras.node, &extras.mesh, &extras.scene, &extras.material];
+        let mut summary = Vec::new();
+        for value in values.into_iter().flatten() {
+            let Some(properties) = value.as_object() else {
+                continue;
+            };
+            for (key, property) in properties {
+                let Some(registration) = registry
+                    .get_with_short_type_path(key)
+                    .or_else(|| registry.get_with_type_path(key))
+                    .filter(|registration| registration.data::<ReflectComponent>().is_some())
+                else {
+                    // Other custom properties are snake_case, so this is most likely a typo
+                    if is_component_key(key) {
+                        let err = format!("no component named {key} is registered");
+                        reader.report(root, entity, err, value);
+                    }
+                    continue;
+                };
+                match reflect_component(registration, &registry, property) {
+                    Ok(component) => {
+                        commands.entity(entity).insert_reflect(component);
+                        summary.push(key.clone());
+                    }
+                    Err(err) => reader.report(root, entity, format!("{key}: {err}"), value),
+                }
+            }
+        }
+        if !summary.is_empty() {
+            reader.summary(root, entity, &summary);
+        }
+    }
+}
+
+// Custom properties named after components start with an uppercase letter, e.g. `Interactable`,
+// while the ones read by a feature module are snake_case
+pub(crate) fn is_component_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+fn reflect_component(
+    registration: &TypeRegistration,
+    registry: &TypeRegistry,
+    value: &serde_json::Value,
+) -> Result<Box<dyn PartialReflect>, String> {
+    let partial = TypedReflectDeserializer::new(registration, registry)
+        .deserialize(value)
+        .map_err(|err| err.to_string())?;
+    let from_reflect = registration
+        .data::<ReflectFromReflect>()
+        .ok_or_else(|| String::from("it can't be built from reflection"))?;
+    from_reflect
+        .from_reflect(&*partial)
+        .map(Reflect::into_partial_reflect)
+        .ok_or_else(|| String::from("some fields are missing and it doesn't reflect Default"))
+}
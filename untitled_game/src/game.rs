@@ -1,105 +1,199 @@
-use crate::menu::MenuState;
-use crate::{despawn_screen, BCollider, BMeshExtra, GameState, Player, PlayerState};
+use crate::bounds::AwaitingSpawn;
+use crate::character::CharacterControllerBundle;
+use crate::extras::{is_component_key, Extras, ExtrasReader, ExtrasReady};
+use crate::inventory::Inventory;
+use crate::level::LevelSource;
+use crate::spawner::{spawn_scene, DespawnPolicy, Spawner};
+use crate::{despawn_screen, BCollider, BMeshExtra, GameLayer, GameState, Player, PlayerState};
 use avian3d::prelude::*;
-use bevy::gltf::GltfMeshExtras;
 use bevy::prelude::*;
-use bevy::scene::SceneInstanceReady;
-use bevy::time::common_conditions::on_timer;
 
 pub fn game_plugin(app: &mut App) {
     app.add_systems(OnEnter(GameState::Game), game_setup)
-        .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>)
-        .add_systems(
-            Update,
-            spawn_cube
-                .run_if(on_timer(std::time::Duration::from_secs(1)))
-                .run_if(in_state(MenuState::Disabled)),
-        );
+        .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>);
 }
 
 // Tag component used to tag entities added on the game screen
 #[derive(Component)]
-struct OnGameScreen;
+pub(crate) struct OnGameScreen;
 
-fn game_setup(
+pub(crate) fn game_setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     asset_server: Res<AssetServer>,
     mut player_state: ResMut<NextState<PlayerState>>,
+    level_source: Res<LevelSource>,
 ) {
     // Player
     let player_id = commands
         .spawn((
             Name::new("Player"),
-            RigidBody::Dynamic,
-            Collider::cylinder(0.3, 1.),
-            Mesh3d(meshes.add(Cylinder::new(0.3, 1.))),
+            CharacterControllerBundle::new(0.3, 1.),
+            Mesh3d(meshes.add(Capsule3d::new(0.3, 0.4))),
             MeshMaterial3d(materials.add(Color::srgb_u8(255, 144, 124))),
-            Transform::from_xyz(5.0, 0.5, 3.0),
+            // Dropped in the middle until it is moved to the level's spawn, which generated
+            // levels don't have
+            Transform::from_xyz(0.0, 3.0, 0.0),
             Player,
+            Inventory::default(),
+            AwaitingSpawn,
             OnGameScreen,
         ))
         .id();
     player_state.set(PlayerState::Id(player_id));
-    // light
+    // Keeps cubes falling in the middle of the level
     commands.spawn((
-        Name::new("Light"),
-        PointLight {
-            shadows_enabled: true,
-            ..default()
+        Name::new("Cube Spawner"),
+        Spawner {
+            max_alive: 20,
+            despawn: DespawnPolicy::KillPlane(-20.),
+            ..Spawner::new(
+                asset_server.load(GltfAssetLabel::Scene(0).from_asset("untitled_game.glb")),
+            )
         },
-        Transform::from_xyz(4.0, 8.0, 4.0),
+        Transform::from_xyz(0., 10., 0.),
         OnGameScreen,
     ));
-    commands
-        .spawn(SceneRoot(asset_server.load(
-            GltfAssetLabel::Scene(1).from_asset("untitled_game.glb"),
-        )))
-        .observe(on_scene_spawn);
+    if *level_source == LevelSource::Fixed {
+        spawn_scene(
+            &mut commands,
+            asset_server.load(GltfAssetLabel::Scene(1).from_asset("untitled_game.glb")),
+            Transform::default(),
+        );
+    }
 }
 
-fn spawn_cube(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands
-        .spawn((
-            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("untitled_game.glb"))),
-            Transform::from_xyz(0., 10., 0.),
-        ))
-        .observe(on_scene_spawn);
+// Blender's dimensions include the object's scale, which also scales the collider, and the
+// custom properties don't get their y and z swapped automatically like meshes
+fn local_lengths(size: Vec3, scale: Vec3) -> Vec3 {
+    Vec3::new(size.x, size.z, size.y) / scale
+}
+
+// Shapes without their lengths are assumed to be Blender's default meshes
+fn collider_constructor(data: &BMeshExtra, scale: Vec3) -> ColliderConstructor {
+    // Round shapes can't be scaled separately on each axis
+    let radius_scale = scale.x.max(scale.z);
+    let round = |default_radius: f32, default_height: f32| {
+        let radius = data
+            .radius
+            .map_or(default_radius, |radius| radius / radius_scale);
+        let height = data
+            .height
+            .map_or(default_height, |height| height / scale.y);
+        (radius, height)
+    };
+    match data.collider {
+        BCollider::TrimeshFromMesh => ColliderConstructor::TrimeshFromMesh,
+        BCollider::ConvexHull => ColliderConstructor::ConvexHullFromMesh,
+        BCollider::ConvexDecomposition => ColliderConstructor::ConvexDecompositionFromMesh,
+        BCollider::Cuboid => {
+            let lengths = data
+                .cube_size
+                .map_or(Vec3::splat(2.), |size| local_lengths(size, scale));
+            ColliderConstructor::Cuboid {
+                x_length: lengths.x,
+                y_length: lengths.y,
+                z_length: lengths.z,
+            }
+        }
+        BCollider::Sphere => ColliderConstructor::Sphere {
+            radius: data
+                .sphere_radius
+                .or(data.radius)
+                .map_or(1., |radius| radius / scale.max_element()),
+        },
+        BCollider::Capsule => {
+            let (radius, height) = round(0.5, 2.);
+            // Avian's height is only the part between the caps
+            ColliderConstructor::Capsule {
+                radius,
+                height: (height - radius * 2.).max(0.),
+            }
+        }
+        BCollider::Cylinder => {
+            let (radius, height) = round(1., 2.);
+            ColliderConstructor::Cylinder { radius, height }
+        }
+    }
+}
+
+fn layer_mask(layers: &[GameLayer]) -> LayerMask {
+    LayerMask(layers.iter().fold(0, |mask, layer| mask | layer.to_bits()))
 }
 
-fn on_scene_spawn(
-    trigger: Trigger<SceneInstanceReady>,
+pub(crate) fn on_scene_spawn(
+    trigger: Trigger<ExtrasReady>,
     mut commands: Commands,
     children: Query<&Children>,
-    extras: Query<&GltfMeshExtras>,
+    parents: Query<&Parent>,
+    transforms: Query<&Transform>,
+    extras: Query<&Extras>,
+    reader: ExtrasReader,
 ) {
-    for entity in children.iter_descendants(trigger.entity()) {
-        if let Ok(gltf_mesh_extra) = extras.get(entity) {
-            if let Ok(data) = serde_json::from_str::<BMeshExtra>(&gltf_mesh_extra.value) {
-                match data.collider {
-                    BCollider::TrimeshFromMesh => {
-                        commands.entity(entity).insert((
-                            RigidBody::from(data.rigid_body),
-                            ColliderConstructor::TrimeshFromMesh,
-                            OnGameScreen,
-                        ));
-                    }
-                    BCollider::Cuboid => {
-                        // let cube_size = data.cube_size.expect("Cube size must be defined with Cuboid");
-                        commands.entity(entity).insert((
-                            RigidBody::from(data.rigid_body),
-                            // custom properties in blender don't get their y and z swapped automatically like meshes
-                            // Collider::cuboid(cube_size.x, cube_size.z, cube_size.y),
-                            // I have no idea why adding a cuboid collider seem to scale the mesh rather than creating one with the size specified
-                            // but because the lengths are divided by two right away we want to pass in all 2s to get a 1:1 scaling of mesh to collider
-                            // ColliderConstructor::Cuboid { x_length: 2., y_length: 2., z_length: 2. },
-                            Collider::cuboid(2., 2., 2.),
-                            OnGameScreen,
-                        ));
-                    }
-                }
-            }
+    let root = trigger.entity();
+    for entity in children.iter_descendants(root) {
+        let Ok(Extras {
+            mesh: Some(serde_json::Value::Object(properties)),
+            ..
+        }) = extras.get(entity)
+        else {
+            continue;
+        };
+        let mut properties = properties.clone();
+        // Components are inserted by the extras plugin, everything else must be physics
+        properties.retain(|key, _| !is_component_key(key));
+        if properties.is_empty() {
+            continue;
+        }
+        let Some(data) = reader.parse_value::<BMeshExtra>(root, entity, &properties.into()) else {
+            continue;
+        };
+        // Global transforms aren't propagated yet when the scene is ready
+        let scale = std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .filter_map(|ancestor| transforms.get(ancestor).ok())
+            .fold(Vec3::ONE, |scale, transform| scale * transform.scale);
+
+        let collider = collider_constructor(&data, scale);
+        let mut summary = vec![
+            format!("{:?} body", data.rigid_body),
+            format!("{collider:?} collider"),
+        ];
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((collider, RigidBody::from(data.rigid_body), OnGameScreen));
+        if let Some(mass) = data.mass {
+            entity_commands.insert(Mass(mass));
+            summary.push(format!("mass {mass}"));
+        }
+        if let Some(density) = data.density {
+            entity_commands.insert(ColliderDensity(density));
+            summary.push(format!("density {density}"));
+        }
+        if let Some(friction) = data.friction {
+            entity_commands.insert(Friction::new(friction));
+            summary.push(format!("friction {friction}"));
+        }
+        if let Some(restitution) = data.restitution {
+            entity_commands.insert(Restitution::new(restitution));
+            summary.push(format!("restitution {restitution}"));
+        }
+        if data.sensor {
+            entity_commands.insert(Sensor);
+            summary.push(String::from("sensor"));
+        }
+        if data.layers.is_some() || data.collides_with.is_some() {
+            entity_commands.insert(CollisionLayers::new(
+                data.layers.as_deref().map_or(LayerMask::ALL, layer_mask),
+                data.collides_with
+                    .as_deref()
+                    .map_or(LayerMask::ALL, layer_mask),
+            ));
+            summary.push(format!(
+                "layers {:?} colliding with {:?}",
+                data.layers, data.collides_with
+            ));
         }
+        reader.summary(root, entity, &summary);
     }
 }
@@ -0,0 +1,172 @@
+//! Gamepad tuning shared with the input actions, and navigation of the menus without a mouse.
+
+use crate::menu::{MenuButtonSystems, MenuState, Rebinding};
+use crate::not_in_state;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+pub fn gamepad_plugin(app: &mut App) {
+    app.init_resource::<GamepadConfig>().add_systems(
+        Update,
+        // Pressed buttons are seen by the menu systems for one frame, then released
+        (release_menu_button, navigate_menu)
+            .chain()
+            .before(MenuButtonSystems)
+            .run_if(not_in_state(MenuState::Disabled)),
+    );
+}
+
+#[derive(Resource, Debug)]
+pub struct GamepadConfig {
+    // Stick values closer to the center than this are ignored, the rest is rescaled to 0..1
+    pub dead_zone: f32,
+    // Look speed at full stick tilt, in radians per second
+    pub look_sensitivity: Vec2,
+    pub invert_look_y: bool,
+    // Menu buttons aren't rebindable, like on consoles
+    pub button_menu_accept: GamepadButton,
+    pub button_menu_back: GamepadButton,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            look_sensitivity: Vec2::new(3.0, 2.0),
+            invert_look_y: false,
+            button_menu_accept: GamepadButton::South,
+            button_menu_back: GamepadButton::East,
+        }
+    }
+}
+
+// Radial dead zone, keeps diagonals the same speed as straight directions
+pub fn apply_dead_zone(stick: Vec2, dead_zone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= dead_zone {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - dead_zone) / (1. - dead_zone)).min(1.);
+    stick / length * scaled
+}
+
+// Marks the button the gamepad focus is on, drawn with an outline
+#[derive(Component)]
+pub struct MenuFocus;
+
+// Marks a button pressed with the gamepad, the mouse isn't there to release it
+#[derive(Component)]
+struct GamepadPressed;
+
+const FOCUS_OUTLINE: Color = Color::srgb(0.9, 0.9, 0.9);
+
+fn release_menu_button(
+    mut commands: Commands,
+    mut pressed: Query<(Entity, &mut Interaction), With<GamepadPressed>>,
+) {
+    for (entity, mut interaction) in &mut pressed {
+        *interaction = Interaction::None;
+        commands.entity(entity).remove::<GamepadPressed>();
+    }
+}
+
+// Moves the focus between buttons in reading order with the d-pad or left stick
+fn navigate_menu(
+    mut commands: Commands,
+    config: Res<GamepadConfig>,
+    gamepads: Query<&Gamepad>,
+    buttons: Query<(Entity, &GlobalTransform, Has<MenuFocus>), With<Button>>,
+    mut interactions: Query<&mut Interaction>,
+    menu_state: Res<State<MenuState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    rebinding: Res<Rebinding>,
+    mut stick_held: Local<bool>,
+) {
+    // The next button press is the new binding, and the one that just set it isn't for the menu
+    if rebinding.0.is_some() || rebinding.is_changed() {
+        return;
+    }
+
+    let mut step = 0;
+    let mut accept = false;
+    let mut back = false;
+    let mut stick = Vec2::ZERO;
+    for gamepad in &gamepads {
+        if gamepad.any_just_pressed([GamepadButton::DPadDown, GamepadButton::DPadRight]) {
+            step += 1;
+        }
+        if gamepad.any_just_pressed([GamepadButton::DPadUp, GamepadButton::DPadLeft]) {
+            step -= 1;
+        }
+        stick += apply_dead_zone(gamepad.left_stick(), config.dead_zone);
+        accept |= gamepad.just_pressed(config.button_menu_accept);
+        back |= gamepad.just_pressed(config.button_menu_back);
+    }
+
+    // The stick only moves the focus once per tilt
+    if stick.length() > 0.5 {
+        if !*stick_held {
+            step += if stick.y < 0. || stick.x > stick.y.abs() {
+                1
+            } else {
+                -1
+            };
+        }
+        *stick_held = true;
+    } else {
+        *stick_held = false;
+    }
+
+    if back {
+        match menu_state.get() {
+            MenuState::Settings | MenuState::Save | MenuState::Load => {
+                next_menu_state.set(MenuState::Main)
+            }
+            MenuState::Controls => next_menu_state.set(MenuState::Settings),
+            MenuState::Inventory => next_menu_state.set(MenuState::Disabled),
+            _ => {}
+        }
+        return;
+    }
+
+    let mut ordered = buttons.iter().collect::<Vec<_>>();
+    if ordered.is_empty() {
+        return;
+    }
+    ordered.sort_by(|(_, a, _), (_, b, _)| {
+        let (a, b) = (a.translation(), b.translation());
+        match a.y.partial_cmp(&b.y) {
+            Some(Ordering::Equal) | None => a.x.total_cmp(&b.x),
+            Some(ordering) => ordering,
+        }
+    });
+
+    let focused = ordered.iter().position(|(_, _, focused)| *focused);
+    let index = match focused {
+        Some(index) => (index as i32 + step).rem_euclid(ordered.len() as i32) as usize,
+        // Nothing is focused until the gamepad is used on a new screen
+        None if step != 0 || accept => 0,
+        None => return,
+    };
+    let (entity, _, _) = ordered[index];
+
+    if focused != Some(index) {
+        if let Some(previous) = focused {
+            commands
+                .entity(ordered[previous].0)
+                .remove::<(MenuFocus, Outline)>();
+        }
+        commands.entity(entity).insert((
+            MenuFocus,
+            Outline::new(Val::Px(3.), Val::ZERO, FOCUS_OUTLINE),
+        ));
+        return;
+    }
+
+    if accept {
+        if let Ok(mut interaction) = interactions.get_mut(entity) {
+            *interaction = Interaction::Pressed;
+            commands.entity(entity).insert(GamepadPressed);
+        }
+    }
+}
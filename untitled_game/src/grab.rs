@@ -0,0 +1,264 @@
+//! Picking up, carrying and throwing dynamic bodies.
+//! A held body is pulled in front of the player every frame by setting its velocities, a spring
+//! that doesn't care about mass, so it still collides with the level instead of going through it.
+//! It stops colliding with the player while it is carried, so it can't push the player around.
+
+use crate::cameras::CameraMode;
+use crate::input::{Action, ActionState};
+use crate::interaction::{find_interactable, InteractionTarget};
+use crate::menu::MenuState;
+use crate::{not_in_state, GameLayer, Player};
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+pub fn grab_plugin(app: &mut App) {
+    app.init_resource::<GrabConfig>()
+        .add_systems(OnExit(CameraMode::FirstPerson), drop_held)
+        .add_systems(OnExit(CameraMode::ThirdPerson), drop_held)
+        .add_systems(
+            Update,
+            (grab_or_drop, rotate_held, carry, throw)
+                .chain()
+                .after(find_interactable)
+                .run_if(in_state(MenuState::Disabled))
+                .run_if(not_in_state(CameraMode::Freecam)),
+        );
+}
+
+#[derive(Resource, Debug)]
+pub struct GrabConfig {
+    // Furthest distance from the player a body can be grabbed from
+    pub max_range: f32,
+    // Distance in front of the player bodies are held at
+    pub hold_distance: f32,
+    // Height above the player's center bodies are held at
+    pub hold_height: f32,
+    // How fast the body is pulled toward where it is held, per second
+    pub stiffness: f32,
+    pub angular_stiffness: f32,
+    // Held bodies further than this from where they should be are dropped, e.g. behind a wall
+    pub break_distance: f32,
+    // Spin speed while rotating, in radians per second
+    pub rotate_speed: f32,
+    // Speed given to thrown bodies, grows with how long throw was held
+    pub min_throw_speed: f32,
+    pub max_throw_speed: f32,
+    // Holding throw longer than this doesn't throw any harder, in seconds
+    pub max_charge_time: f32,
+}
+
+impl Default for GrabConfig {
+    fn default() -> Self {
+        Self {
+            max_range: 3.,
+            hold_distance: 1.5,
+            hold_height: 0.4,
+            stiffness: 15.,
+            angular_stiffness: 10.,
+            break_distance: 2.5,
+            rotate_speed: 2.,
+            min_throw_speed: 3.,
+            max_throw_speed: 15.,
+            max_charge_time: 1.,
+        }
+    }
+}
+
+// On the body the player is holding
+#[derive(Component, Debug)]
+pub struct Held {
+    pub holder: Entity,
+    // Rotation relative to the camera's yaw, so the body turns with the player
+    pub rotation: Quat,
+    // How long throw has been held, in seconds
+    pub charge: f32,
+    // What the body had before it was grabbed, put back when it is released
+    pub gravity_scale: Option<GravityScale>,
+    pub collision_layers: Vec<(Entity, Option<CollisionLayers>)>,
+}
+
+fn camera_yaw(camera: &GlobalTransform) -> Quat {
+    let forward = camera.forward();
+    Quat::from_rotation_y(forward.x.atan2(forward.z) + std::f32::consts::PI)
+}
+
+fn hold_point(config: &GrabConfig, player: &GlobalTransform, camera: &GlobalTransform) -> Vec3 {
+    player.translation() + Vec3::Y * config.hold_height + camera.forward() * config.hold_distance
+}
+
+fn release(commands: &mut Commands, entity: Entity, held: &Held) {
+    let mut body = commands.entity(entity);
+    body.remove::<Held>();
+    match held.gravity_scale {
+        Some(gravity_scale) => body.insert(gravity_scale),
+        None => body.remove::<GravityScale>(),
+    };
+    for (collider, layers) in &held.collision_layers {
+        // The body can have lost colliders while it was held
+        let Some(mut collider) = commands.get_entity(*collider) else {
+            continue;
+        };
+        match layers {
+            Some(layers) => collider.insert(*layers),
+            None => collider.remove::<CollisionLayers>(),
+        };
+    }
+}
+
+// Interact grabs the dynamic body looked at, unless it is an interactable, and drops it again
+#[allow(clippy::too_many_arguments)]
+fn grab_or_drop(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    config: Res<GrabConfig>,
+    target: Res<InteractionTarget>,
+    camera: Single<&GlobalTransform, With<Camera3d>>,
+    player: Single<(Entity, &GlobalTransform), With<Player>>,
+    collider_parents: Query<&ColliderParent>,
+    bodies: Query<(&RigidBody, &Rotation, Option<&GravityScale>)>,
+    children: Query<&Children>,
+    colliders: Query<Option<&CollisionLayers>, With<Collider>>,
+    held: Query<(Entity, &Held)>,
+    spatial_query: SpatialQuery,
+) {
+    if !actions.just_pressed(Action::Interact) {
+        return;
+    }
+    if let Ok((entity, held)) = held.get_single() {
+        release(&mut commands, entity, held);
+        return;
+    }
+    if target.0.is_some() {
+        return;
+    }
+
+    let (player, player_transform) = player.into_inner();
+    let origin = camera.translation();
+    let direction = camera.forward();
+    let filter = SpatialQueryFilter::default().with_excluded_entities([player]);
+    let Some(hit) = spatial_query.cast_ray(origin, direction, 100., true, &filter) else {
+        return;
+    };
+    if (origin + direction * hit.distance).distance(player_transform.translation())
+        > config.max_range
+    {
+        return;
+    }
+    // Colliders can be children of their body
+    let body = collider_parents
+        .get(hit.entity)
+        .map_or(hit.entity, ColliderParent::get);
+    let Ok((rigid_body, rotation, gravity_scale)) = bodies.get(body) else {
+        return;
+    };
+    if !rigid_body.is_dynamic() {
+        return;
+    }
+
+    let collision_layers = std::iter::once(body)
+        .chain(children.iter_descendants(body))
+        .filter_map(|entity| Some((entity, colliders.get(entity).ok()?.copied())))
+        .collect::<Vec<(Entity, Option<CollisionLayers>)>>();
+    for (collider, layers) in &collision_layers {
+        let layers = layers.unwrap_or_default();
+        commands.entity(*collider).insert(CollisionLayers::new(
+            layers.memberships,
+            LayerMask(layers.filters.0 & !GameLayer::Player.to_bits()),
+        ));
+    }
+    commands.entity(body).insert((
+        Held {
+            holder: player,
+            rotation: camera_yaw(&camera).inverse() * rotation.0,
+            charge: 0.,
+            gravity_scale: gravity_scale.copied(),
+            collision_layers,
+        },
+        // The spring holds it up, gravity would only make it sag
+        GravityScale(0.),
+    ));
+}
+
+fn rotate_held(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    config: Res<GrabConfig>,
+    mut held: Query<&mut Held>,
+) {
+    if !actions.pressed(Action::RotateHeld) {
+        return;
+    }
+    for mut held in &mut held {
+        held.rotation =
+            Quat::from_rotation_y(config.rotate_speed * time.delta_secs()) * held.rotation;
+    }
+}
+
+fn carry(
+    mut commands: Commands,
+    config: Res<GrabConfig>,
+    camera: Single<&GlobalTransform, With<Camera3d>>,
+    players: Query<&GlobalTransform, With<Player>>,
+    mut held: Query<(
+        Entity,
+        &Held,
+        &Position,
+        &Rotation,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+    )>,
+) {
+    for (entity, held, position, rotation, mut velocity, mut angular_velocity) in &mut held {
+        let Ok(player) = players.get(held.holder) else {
+            release(&mut commands, entity, held);
+            continue;
+        };
+        let target = hold_point(&config, player, &camera);
+        let offset = target - position.0;
+        if offset.length() > config.break_distance {
+            release(&mut commands, entity, held);
+            continue;
+        }
+        velocity.0 = offset * config.stiffness;
+
+        let target_rotation = camera_yaw(&camera) * held.rotation;
+        let mut difference = target_rotation * rotation.0.inverse();
+        // Turn the short way around
+        if difference.w < 0. {
+            difference = -difference;
+        }
+        angular_velocity.0 = difference.to_scaled_axis() * config.angular_stiffness;
+    }
+}
+
+// Holding throw charges it, releasing it throws the held body where the camera looks
+fn throw(
+    mut commands: Commands,
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    config: Res<GrabConfig>,
+    camera: Single<&GlobalTransform, With<Camera3d>>,
+    mut held: Query<(Entity, &mut Held, &ComputedMass)>,
+) {
+    for (entity, mut held, mass) in &mut held {
+        if actions.pressed(Action::Throw) {
+            held.charge += time.delta_secs();
+            continue;
+        }
+        if held.charge <= 0. {
+            continue;
+        }
+        let charge = (held.charge / config.max_charge_time).min(1.);
+        let speed = config.min_throw_speed.lerp(config.max_throw_speed, charge);
+        release(&mut commands, entity, &held);
+        commands.entity(entity).insert(ExternalImpulse::new(
+            camera.forward() * speed * mass.value(),
+        ));
+    }
+}
+
+fn drop_held(mut commands: Commands, held: Query<(Entity, &Held)>) {
+    for (entity, held) in &held {
+        release(&mut commands, entity, held);
+    }
+}
@@ -0,0 +1,247 @@
+//! Applies the `DisplayQuality` setting to the renderer, live while it is changed in the menu.
+//! The 3D camera renders to an image at a fraction of the window's resolution, which a 2D camera
+//! stretches over the window before drawing the UI on top at full resolution.
+//! Also makes lights with a [`PointLightFlicker`] flicker, which is set up in Blender with a custom
+//! property on the light, e.g. `{"PointLightFlicker": {"amount": 0.3}}`
+
+use crate::DisplayQuality;
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::PointLightShadowMap;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+
+pub fn graphics_plugin(app: &mut App) {
+    app.register_type::<PointLightFlicker>()
+        .add_systems(Startup, setup_scaled_view)
+        .add_systems(
+            Update,
+            (
+                apply_camera_settings.run_if(resource_changed::<DisplayQuality>),
+                render_to_scaled_view,
+                apply_render_scale,
+                apply_light_settings,
+                flicker_lights,
+            ),
+        );
+}
+
+// What a `DisplayQuality` level means for the renderer
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    pub shadows_enabled: bool,
+    // Resolution of each face of the point light shadow cubemaps
+    pub shadow_map_size: usize,
+    pub msaa: Msaa,
+    pub skybox_brightness: f32,
+    // Far plane of the camera, anything further away isn't drawn
+    pub view_distance: f32,
+    // Resolution of the 3D view relative to the window, the UI is always at full resolution
+    pub render_scale: f32,
+}
+
+impl DisplayQuality {
+    pub fn render_settings(self) -> RenderSettings {
+        match self {
+            DisplayQuality::Low => RenderSettings {
+                shadows_enabled: false,
+                shadow_map_size: 256,
+                msaa: Msaa::Off,
+                skybox_brightness: 800.,
+                view_distance: 150.,
+                render_scale: 0.5,
+            },
+            DisplayQuality::Medium => RenderSettings {
+                shadows_enabled: true,
+                shadow_map_size: 1024,
+                msaa: Msaa::Sample2,
+                skybox_brightness: 1000.,
+                view_distance: 500.,
+                render_scale: 0.75,
+            },
+            DisplayQuality::High => RenderSettings {
+                shadows_enabled: true,
+                shadow_map_size: 4096,
+                msaa: Msaa::Sample4,
+                skybox_brightness: 1000.,
+                view_distance: 1000.,
+                render_scale: 1.,
+            },
+        }
+    }
+}
+
+fn apply_camera_settings(
+    display_quality: Res<DisplayQuality>,
+    mut shadow_map: ResMut<PointLightShadowMap>,
+    mut cameras: Query<(&mut Msaa, &mut Projection, Option<&mut Skybox>), With<Camera3d>>,
+) {
+    let settings = display_quality.render_settings();
+    shadow_map.size = settings.shadow_map_size;
+    for (mut msaa, mut projection, skybox) in &mut cameras {
+        *msaa = settings.msaa;
+        if let Projection::Perspective(perspective) = &mut *projection {
+            perspective.far = settings.view_distance;
+        }
+        if let Some(mut skybox) = skybox {
+            skybox.brightness = settings.skybox_brightness;
+        }
+    }
+}
+
+// The image the 3D camera renders to, resized with the window and the render scale
+#[derive(Resource, Debug)]
+struct ScaledView(Handle<Image>);
+
+// Sprite showing the `ScaledView` over the whole window
+#[derive(Component, Debug)]
+struct ScaledViewSprite;
+
+fn setup_scaled_view(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    // Sized by `apply_render_scale` once the window is known
+    let mut image = Image::new_fill(
+        Extent3d::default(),
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    let image = images.add(image);
+
+    commands.spawn((
+        Name::new("Scaled view camera"),
+        Camera2d,
+        // After the 3D camera, which may still draw to the window on the first frame
+        Camera {
+            order: 1,
+            ..default()
+        },
+        Msaa::Off,
+        IsDefaultUiCamera,
+    ));
+    commands.spawn((ScaledViewSprite, Sprite::from_image(image.clone())));
+    commands.insert_resource(ScaledView(image));
+}
+
+fn render_to_scaled_view(
+    scaled_view: Res<ScaledView>,
+    mut cameras: Query<&mut Camera, Added<Camera3d>>,
+) {
+    for mut camera in &mut cameras {
+        camera.target = RenderTarget::Image(scaled_view.0.clone());
+    }
+}
+
+fn apply_render_scale(
+    display_quality: Res<DisplayQuality>,
+    scaled_view: Res<ScaledView>,
+    window: Single<Ref<Window>>,
+    mut images: ResMut<Assets<Image>>,
+    mut sprite: Single<&mut Sprite, With<ScaledViewSprite>>,
+) {
+    if !display_quality.is_changed() && !window.is_changed() {
+        return;
+    }
+    // The 2D camera has one unit per logical pixel
+    if sprite.custom_size != Some(window.size()) {
+        sprite.custom_size = Some(window.size());
+    }
+    let scale = display_quality.render_settings().render_scale;
+    let size = (window.physical_size().as_vec2() * scale)
+        .round()
+        .as_uvec2()
+        .max(UVec2::ONE);
+    // Checked first, getting the image mutably uploads it again
+    if images
+        .get(&scaled_view.0)
+        .is_some_and(|image| image.size() != size)
+    {
+        if let Some(image) = images.get_mut(&scaled_view.0) {
+            image.resize(Extent3d {
+                width: size.x,
+                height: size.y,
+                ..default()
+            });
+        }
+    }
+}
+
+// Whether a light was set up with shadows, the display quality can only turn them off
+#[derive(Component, Debug)]
+struct AuthoredShadows(bool);
+
+// Lights are spawned with the levels, so they are updated when they appear too
+fn apply_light_settings(
+    mut commands: Commands,
+    display_quality: Res<DisplayQuality>,
+    mut lights: Query<(Entity, &mut PointLight, Option<&AuthoredShadows>)>,
+) {
+    let settings = display_quality.render_settings();
+    for (entity, mut light, authored) in &mut lights {
+        let authored = match authored {
+            Some(authored) if display_quality.is_changed() => authored.0,
+            Some(_) => continue,
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(AuthoredShadows(light.shadows_enabled));
+                light.shadows_enabled
+            }
+        };
+        light.shadows_enabled = authored && settings.shadows_enabled;
+    }
+}
+
+// Varies the intensity of the `PointLight` on the same entity or its children, Blender exports
+// lights as a child of the object the custom properties are on
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component, Default)]
+pub struct PointLightFlicker {
+    // How much of the intensity can be lost, from 0 to 1
+    pub amount: f32,
+    // Roughly how many flickers there are per second
+    pub speed: f32,
+}
+
+impl Default for PointLightFlicker {
+    fn default() -> Self {
+        Self {
+            amount: 0.2,
+            speed: 8.,
+        }
+    }
+}
+
+// Intensity of a flickering light as it was set up, before any flicker
+#[derive(Component, Debug)]
+struct BaseIntensity(f32);
+
+fn flicker_lights(
+    mut commands: Commands,
+    time: Res<Time>,
+    flickers: Query<(Entity, &PointLightFlicker, Option<&Children>)>,
+    mut lights: Query<(&mut PointLight, Option<&BaseIntensity>)>,
+) {
+    for (entity, flicker, children) in &flickers {
+        // Offset by the entity so lights don't flicker in sync
+        let t = time.elapsed_secs() * flicker.speed + entity.index() as f32;
+        // Sines of unrelated frequencies, which is irregular enough for a flame, in 0..=1
+        let noise = (t.sin() + (t * 2.3).sin() * 0.5 + (t * 5.7).sin() * 0.25) / 3.5 + 0.5;
+        let light_entities = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        for light_entity in light_entities {
+            let Ok((mut light, base)) = lights.get_mut(light_entity) else {
+                continue;
+            };
+            let Some(base) = base else {
+                commands
+                    .entity(light_entity)
+                    .insert(BaseIntensity(light.intensity));
+                continue;
+            };
+            light.intensity = base.0 * (1. - flicker.amount * noise);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Maps keyboard, mouse and gamepad buttons to game [`Action`]s.
+//! Gameplay reads the resulting [`ActionState`] instead of raw inputs, the bindings live in the
+//! [`InputMap`] which is rebound from the controls menu and persisted between runs.
+
+use crate::gamepad::{apply_dead_zone, GamepadConfig};
+use crate::storage;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BINDINGS_KEY: &str = "bindings.json";
+
+pub fn input_plugin(app: &mut App) {
+    app.insert_resource(InputMap::load())
+        .init_resource::<ActionState>()
+        .add_systems(PreUpdate, update_action_state.after(InputSystem))
+        .add_systems(Update, save_input_map.run_if(resource_changed::<InputMap>));
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Run,
+    Jump,
+    Interact,
+    // Hold to charge, release to throw what is held
+    Throw,
+    // Spins what is held while pressed
+    RotateHeld,
+    // Opens and closes the inventory screen
+    Inventory,
+    // Cycle the selected hotbar slot
+    NextItem,
+    PreviousItem,
+    Pause,
+    SwapShoulder,
+    CycleCamera,
+    // Only used by the freecam
+    FlyUp,
+    FlyDown,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Run,
+        Action::Jump,
+        Action::Interact,
+        Action::Throw,
+        Action::RotateHeld,
+        Action::Inventory,
+        Action::NextItem,
+        Action::PreviousItem,
+        Action::Pause,
+        Action::SwapShoulder,
+        Action::CycleCamera,
+        Action::FlyUp,
+        Action::FlyDown,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "Move Forward",
+            Action::MoveBack => "Move Back",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Run => "Run",
+            Action::Jump => "Jump",
+            Action::Interact => "Interact",
+            Action::Throw => "Throw",
+            Action::RotateHeld => "Rotate Held",
+            Action::Inventory => "Inventory",
+            Action::NextItem => "Next Item",
+            Action::PreviousItem => "Previous Item",
+            Action::Pause => "Pause",
+            Action::SwapShoulder => "Swap Shoulder",
+            Action::CycleCamera => "Camera Mode",
+            Action::FlyUp => "Fly Up",
+            Action::FlyDown => "Fly Down",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+// Every action has up to one binding per slot, the controls menu shows one column per slot
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingSlot {
+    KeyboardMouse,
+    Gamepad,
+}
+
+impl Binding {
+    pub fn slot(&self) -> BindingSlot {
+        match self {
+            Binding::Key(_) | Binding::Mouse(_) => BindingSlot::KeyboardMouse,
+            Binding::Gamepad(_) => BindingSlot::Gamepad,
+        }
+    }
+
+    // Short name to show to the player, e.g. "W" instead of "KeyW"
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                name.strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .map(String::from)
+                    .unwrap_or(name)
+            }
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+            Binding::Gamepad(button) => format!("{button:?}"),
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputMap {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::*;
+        let bindings = Action::ALL.map(|action| {
+            let bindings = match action {
+                Action::MoveForward => vec![Key(KeyCode::KeyW), Gamepad(GamepadButton::DPadUp)],
+                Action::MoveBack => vec![Key(KeyCode::KeyS), Gamepad(GamepadButton::DPadDown)],
+                Action::MoveLeft => vec![Key(KeyCode::KeyA), Gamepad(GamepadButton::DPadLeft)],
+                Action::MoveRight => vec![Key(KeyCode::KeyD), Gamepad(GamepadButton::DPadRight)],
+                Action::Run => vec![
+                    Key(KeyCode::ShiftLeft),
+                    Gamepad(GamepadButton::LeftTrigger2),
+                ],
+                Action::Jump => vec![Key(KeyCode::Space), Gamepad(GamepadButton::South)],
+                Action::Interact => vec![Key(KeyCode::KeyE), Gamepad(GamepadButton::RightTrigger2)],
+                Action::Throw => vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButton::RightTrigger),
+                ],
+                Action::RotateHeld => vec![Key(KeyCode::KeyR), Gamepad(GamepadButton::North)],
+                Action::Inventory => vec![Key(KeyCode::Tab), Gamepad(GamepadButton::LeftThumb)],
+                Action::NextItem => vec![Key(KeyCode::KeyX), Gamepad(GamepadButton::West)],
+                Action::PreviousItem => {
+                    vec![Key(KeyCode::KeyZ), Gamepad(GamepadButton::LeftTrigger)]
+                }
+                Action::Pause => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Start)],
+                Action::SwapShoulder => {
+                    vec![Key(KeyCode::KeyQ), Gamepad(GamepadButton::RightThumb)]
+                }
+                Action::CycleCamera => vec![Key(KeyCode::KeyV), Gamepad(GamepadButton::Select)],
+                Action::FlyUp => vec![Key(KeyCode::Space), Gamepad(GamepadButton::South)],
+                Action::FlyDown => vec![Key(KeyCode::ControlLeft), Gamepad(GamepadButton::East)],
+            };
+            (action, bindings)
+        });
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl InputMap {
+    // Saved bindings, with defaults for the actions added since they were saved
+    pub fn load() -> Self {
+        let mut input_map = Self::default();
+        if let Some(contents) = storage::load(BINDINGS_KEY) {
+            match serde_json::from_str::<InputMap>(&contents) {
+                Ok(saved) => input_map.bindings.extend(saved.bindings),
+                Err(err) => warn!("Ignoring saved bindings: {err}"),
+            }
+        }
+        input_map
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn binding(&self, action: Action, slot: BindingSlot) -> Option<Binding> {
+        self.bindings(action)
+            .iter()
+            .copied()
+            .find(|binding| binding.slot() == slot)
+    }
+
+    // Replaces the action's binding in the slot of the new binding
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|existing| existing.slot() != binding.slot());
+        bindings.push(binding);
+    }
+
+    // Label of the first binding, used in on screen prompts
+    pub fn label(&self, action: Action) -> String {
+        self.bindings(action)
+            .first()
+            .map_or_else(|| String::from("unbound"), Binding::label)
+    }
+}
+
+// What the player is doing this frame, updated before `Update` from the `InputMap`
+#[derive(Resource, Default, Debug)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    // Move actions and the left stick combined, x is right and y is forward, with a length of
+    // at most 1
+    pub movement: Vec2,
+    // Right stick already scaled by the sensitivity and frame time, in radians
+    // Positive x turns right and positive y looks up, the mouse is read by each camera
+    pub look: Vec2,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    fn axis(&self, negative: Action, positive: Action) -> f32 {
+        self.pressed(positive) as i32 as f32 - self.pressed(negative) as i32 as f32
+    }
+}
+
+fn update_action_state(
+    time: Res<Time>,
+    input_map: Res<InputMap>,
+    config: Res<GamepadConfig>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+) {
+    let pressed = |binding: &Binding| match binding {
+        Binding::Key(key) => key_input.pressed(*key),
+        Binding::Mouse(button) => mouse_input.pressed(*button),
+        Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(*button)),
+    };
+    let just_pressed = |binding: &Binding| match binding {
+        Binding::Key(key) => key_input.just_pressed(*key),
+        Binding::Mouse(button) => mouse_input.just_pressed(*button),
+        Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.just_pressed(*button)),
+    };
+
+    state.pressed.clear();
+    state.just_pressed.clear();
+    for (action, bindings) in &input_map.bindings {
+        if bindings.iter().any(pressed) {
+            state.pressed.insert(*action);
+        }
+        if bindings.iter().any(just_pressed) {
+            state.just_pressed.insert(*action);
+        }
+    }
+
+    let mut movement = Vec2::new(
+        state.axis(Action::MoveLeft, Action::MoveRight),
+        state.axis(Action::MoveBack, Action::MoveForward),
+    );
+    let mut look = Vec2::ZERO;
+    for gamepad in &gamepads {
+        movement += apply_dead_zone(gamepad.left_stick(), config.dead_zone);
+        look += apply_dead_zone(gamepad.right_stick(), config.dead_zone);
+    }
+    if config.invert_look_y {
+        look.y = -look.y;
+    }
+    state.movement = movement.clamp_length_max(1.);
+    state.look = look * config.look_sensitivity * time.delta_secs();
+}
+
+fn save_input_map(input_map: Res<InputMap>) {
+    // Loading it at startup isn't a change worth writing back
+    if input_map.is_added() {
+        return;
+    }
+    match serde_json::to_string_pretty(&*input_map) {
+        Ok(contents) => {
+            if let Err(err) = storage::save(BINDINGS_KEY, &contents) {
+                error!("Failed to save bindings: {err}");
+            }
+        }
+        Err(err) => error!("Failed to serialize bindings: {err}"),
+    }
+}
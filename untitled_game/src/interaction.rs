@@ -0,0 +1,376 @@
+//! Objects the player can interact with by looking at them and pressing the interact action.
+//! An [`Interactable`] only says that an entity can be interacted with, what happens is up to
+//! the observers of [`Interacted`], like the built-in doors, switches and pickups.
+//! In Blender they are set up with custom properties on the object, e.g.
+//! `{"interactable": {"prompt": "Open"}, "door": {"open_angle": 90}}`, or with the reflected
+//! component itself, e.g. `{"Interactable": {"prompt": "Read"}}`
+
+use crate::cameras::third_person::CameraUIState;
+use crate::cameras::{CameraMode, OnCameraUIInteract};
+use crate::extras::{Extras, ExtrasReader, ExtrasReady};
+use crate::input::{Action, ActionState, InputMap};
+use crate::menu::MenuState;
+use crate::{despawn_screen, not_in_state, Player};
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn interaction_plugin(app: &mut App) {
+    app.init_resource::<InteractionTarget>()
+        .register_type::<Interactable>()
+        .add_event::<PickedUp>()
+        .add_observer(spawn_interactables)
+        .add_observer(toggle_door)
+        .add_observer(toggle_switch)
+        .add_observer(pick_up)
+        .add_systems(OnEnter(CameraUIState::Visible), interaction_display_setup)
+        .add_systems(
+            OnExit(CameraUIState::Visible),
+            despawn_screen::<OnCameraUIInteract>,
+        )
+        .add_systems(
+            Update,
+            (find_interactable, interact, update_prompt)
+                .chain()
+                .run_if(in_state(MenuState::Disabled))
+                .run_if(not_in_state(CameraMode::Freecam)),
+        )
+        .add_systems(Update, animate_doors);
+}
+
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component, Default)]
+pub struct Interactable {
+    // Shown after the key to press, e.g. "Open" for "Press E to Open"
+    pub prompt: String,
+    // Furthest distance from the player it can be interacted from
+    pub max_range: f32,
+}
+
+impl Default for Interactable {
+    fn default() -> Self {
+        Self {
+            prompt: String::from("Interact"),
+            max_range: 3.,
+        }
+    }
+}
+
+// Triggered on an `Interactable` entity when the player interacts with it
+#[derive(Event, Debug)]
+pub struct Interacted {
+    // Who interacted, usually the player
+    pub by: Entity,
+}
+
+// The interactable the player is currently looking at
+#[derive(Resource, Default, Debug)]
+pub struct InteractionTarget(pub Option<Entity>);
+
+// Swings around its origin when interacted with
+#[derive(Component, Debug)]
+pub struct Door {
+    pub open: bool,
+    // Rotation around the up axis when open, in radians
+    pub open_angle: f32,
+    pub closed_rotation: Quat,
+}
+
+// Interacts with its targets when it is interacted with, e.g. to open doors from afar
+#[derive(Component, Debug)]
+pub struct Switch {
+    pub on: bool,
+    pub targets: Vec<Entity>,
+}
+
+// Sends a `PickedUp` event when interacted with, it is up to whoever takes the item to
+// despawn it, so it stays in the world when it doesn't fit anywhere
+#[derive(Component, Debug)]
+pub struct Pickup {
+    // Id of the item definition
+    pub item: String,
+    pub count: u32,
+}
+
+#[derive(Event, Debug)]
+pub struct PickedUp {
+    // The entity with the `Pickup`
+    pub pickup: Entity,
+    pub item: String,
+    pub count: u32,
+    pub by: Entity,
+}
+
+// B stands for blender, every field is an optional custom property of the object
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct BInteractExtra {
+    pub interactable: Option<BInteractable>,
+    pub door: Option<BDoor>,
+    pub switch: Option<BSwitch>,
+    pub pickup: Option<BPickup>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BInteractable {
+    pub prompt: Option<String>,
+    pub max_range: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BDoor {
+    // In degrees, like Blender shows rotations
+    pub open_angle: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BSwitch {
+    // Names of the objects in the same scene to interact with
+    pub targets: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BPickup {
+    pub item: String,
+    pub count: Option<u32>,
+}
+
+// Doors, switches and pickups are interactable even without an `interactable` property
+fn spawn_interactables(
+    trigger: Trigger<ExtrasReady>,
+    mut commands: Commands,
+    children: Query<&Children>,
+    extras: Query<(&Extras, &Transform)>,
+    names: Query<(Entity, &Name)>,
+    reader: ExtrasReader,
+) {
+    let root = trigger.entity();
+    for entity in children.iter_descendants(root) {
+        let Ok((Extras { node, .. }, transform)) = extras.get(entity) else {
+            continue;
+        };
+        let Some(node) = node else {
+            continue;
+        };
+        let Some(data) = reader.parse_value::<BInteractExtra>(root, entity, node) else {
+            continue;
+        };
+
+        let mut interactable = None;
+        if let Some(door) = data.door {
+            commands.entity(entity).insert(Door {
+                open: false,
+                open_angle: door.open_angle.unwrap_or(90.).to_radians(),
+                closed_rotation: transform.rotation,
+            });
+            interactable = Some(String::from("Open"));
+        }
+        if let Some(switch) = data.switch {
+            let targets = children
+                .iter_descendants(trigger.entity())
+                .filter_map(|child| names.get(child).ok())
+                .filter(|(_, name)| switch.targets.iter().any(|target| target == name.as_str()))
+                .map(|(target, _)| target)
+                .collect::<Vec<Entity>>();
+            if targets.len() < switch.targets.len() {
+                warn!(
+                    "Some targets of switch {entity} are not in its scene: {:?}",
+                    switch.targets
+                );
+            }
+            commands
+                .entity(entity)
+                .insert(Switch { on: false, targets });
+            interactable = Some(String::from("Use"));
+        }
+        if let Some(pickup) = data.pickup {
+            interactable = Some(format!("Pick up {}", pickup.item));
+            commands.entity(entity).insert(Pickup {
+                item: pickup.item,
+                count: pickup.count.unwrap_or(1),
+            });
+        }
+
+        let prompt = data
+            .interactable
+            .as_ref()
+            .and_then(|interactable| interactable.prompt.clone())
+            .or(interactable);
+        if let Some(prompt) = prompt {
+            let mut component = Interactable {
+                prompt,
+                ..default()
+            };
+            if let Some(max_range) = data
+                .interactable
+                .and_then(|interactable| interactable.max_range)
+            {
+                component.max_range = max_range;
+            }
+            commands.entity(entity).insert(component);
+        }
+    }
+}
+
+// Colliders are usually on a mesh below the object with the properties
+fn interactable_ancestor(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    interactables: &Query<&Interactable>,
+) -> Option<Entity> {
+    std::iter::once(entity)
+        .chain(parents.iter_ancestors(entity))
+        .find(|ancestor| interactables.contains(*ancestor))
+}
+
+pub(crate) fn find_interactable(
+    camera: Single<&GlobalTransform, With<Camera3d>>,
+    player: Single<(Entity, &GlobalTransform), With<Player>>,
+    spatial_query: SpatialQuery,
+    parents: Query<&Parent>,
+    interactables: Query<&Interactable>,
+    mut target: ResMut<InteractionTarget>,
+    mut camera_ui_state: ResMut<NextState<CameraUIState>>,
+) {
+    let (player, player_transform) = player.into_inner();
+    let origin = camera.translation();
+    let direction = camera.forward();
+    // The first person camera is inside the player's collider
+    let filter = SpatialQueryFilter::default().with_excluded_entities([player]);
+
+    target.0 = spatial_query
+        .cast_ray(origin, direction, 100., true, &filter)
+        .and_then(|hit| {
+            let entity = interactable_ancestor(hit.entity, &parents, &interactables)?;
+            let interactable = interactables.get(entity).ok()?;
+            let point = origin + direction * hit.distance;
+            (point.distance(player_transform.translation()) <= interactable.max_range)
+                .then_some(entity)
+        });
+
+    camera_ui_state.set(if target.0.is_some() {
+        CameraUIState::Visible
+    } else {
+        CameraUIState::Hidden
+    });
+}
+
+fn interact(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    target: Res<InteractionTarget>,
+    player: Single<Entity, With<Player>>,
+) {
+    if let Some(entity) = target.0 {
+        if actions.just_pressed(Action::Interact) {
+            commands.trigger_targets(Interacted { by: *player }, entity);
+        }
+    }
+}
+
+// Marks the text of the interaction prompt
+#[derive(Component)]
+struct InteractPrompt;
+
+fn prompt_text(input_map: &InputMap, interactable: Option<&Interactable>) -> String {
+    format!(
+        "Press {} to {}",
+        input_map.label(Action::Interact),
+        interactable.map_or("Interact", |interactable| interactable.prompt.as_str())
+    )
+}
+
+fn interaction_display_setup(
+    mut commands: Commands,
+    input_map: Res<InputMap>,
+    target: Res<InteractionTarget>,
+    interactables: Query<&Interactable>,
+) {
+    let interactable = target.0.and_then(|entity| interactables.get(entity).ok());
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::End,
+                justify_content: JustifyContent::End,
+                ..default()
+            },
+            OnCameraUIInteract,
+        ))
+        .with_child((
+            Text::new(prompt_text(&input_map, interactable)),
+            InteractPrompt,
+        ));
+}
+
+// The prompt changes when looking from one interactable to another, or when a door opens
+fn update_prompt(
+    input_map: Res<InputMap>,
+    target: Res<InteractionTarget>,
+    interactables: Query<&Interactable>,
+    mut texts: Query<&mut Text, With<InteractPrompt>>,
+) {
+    let interactable = target.0.and_then(|entity| interactables.get(entity).ok());
+    let prompt = prompt_text(&input_map, interactable);
+    for mut text in &mut texts {
+        if text.0 != prompt {
+            text.0 = prompt.clone();
+        }
+    }
+}
+
+fn toggle_door(trigger: Trigger<Interacted>, mut doors: Query<(&mut Door, &mut Interactable)>) {
+    if let Ok((mut door, mut interactable)) = doors.get_mut(trigger.entity()) {
+        door.open = !door.open;
+        interactable.prompt = String::from(if door.open { "Close" } else { "Open" });
+    }
+}
+
+fn animate_doors(time: Res<Time>, mut doors: Query<(&Door, &mut Transform)>) {
+    for (door, mut transform) in &mut doors {
+        let target = if door.open {
+            door.closed_rotation * Quat::from_rotation_y(door.open_angle)
+        } else {
+            door.closed_rotation
+        };
+        transform
+            .rotation
+            .smooth_nudge(&target, 6., time.delta_secs());
+    }
+}
+
+fn toggle_switch(
+    trigger: Trigger<Interacted>,
+    mut commands: Commands,
+    mut switches: Query<&mut Switch>,
+) {
+    if let Ok(mut switch) = switches.get_mut(trigger.entity()) {
+        switch.on = !switch.on;
+        // Without targets the event would go to every `Interacted` observer
+        if switch.targets.is_empty() {
+            return;
+        }
+        commands.trigger_targets(
+            Interacted {
+                by: trigger.event().by,
+            },
+            switch.targets.clone(),
+        );
+    }
+}
+
+fn pick_up(
+    trigger: Trigger<Interacted>,
+    pickups: Query<&Pickup>,
+    mut picked_up: EventWriter<PickedUp>,
+) {
+    if let Ok(pickup) = pickups.get(trigger.entity()) {
+        picked_up.send(PickedUp {
+            pickup: trigger.entity(),
+            item: pickup.item.clone(),
+            count: pickup.count,
+            by: trigger.event().by,
+        });
+    }
+}
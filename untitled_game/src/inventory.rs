@@ -0,0 +1,573 @@
+//! What the player carries. Items are defined in `*.items.json` assets that map an item id to
+//! its [`ItemDefinition`], the [`Inventory`] only stores ids and counts so it can be saved as is.
+//! Items get into an inventory by interacting with a [`Pickup`], the first row of slots is the
+//! hotbar shown during the game, all of them are on the inventory screen.
+
+use crate::input::{Action, ActionState};
+use crate::interaction::{Interactable, PickedUp, Pickup};
+use crate::menu::{MenuButtonSystems, MenuState, SelectedOption, NORMAL_BUTTON, TEXT_COLOR};
+use crate::{capture_cursor, despawn_screen, GameState, Player};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use bevy::window::CursorGrabMode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Slots in the first row, the ones the selected item is picked from
+pub const HOTBAR_SIZE: usize = 8;
+pub const INVENTORY_SIZE: usize = 3 * HOTBAR_SIZE;
+
+const SLOT_BORDER: Color = Color::srgb(0.3, 0.3, 0.3);
+const SELECTED_SLOT_BORDER: Color = Color::srgb(0.9, 0.9, 0.9);
+
+pub fn inventory_plugin(app: &mut App) {
+    app.init_resource::<CollectedPickups>()
+        .init_asset::<ItemCatalog>()
+        .init_asset_loader::<ItemCatalogLoader>()
+        .add_systems(Startup, load_items)
+        .add_systems(
+            OnEnter(MenuState::Disabled),
+            hotbar_setup.run_if(in_state(GameState::Game)),
+        )
+        .add_systems(OnExit(MenuState::Disabled), despawn_screen::<OnHotbar>)
+        .add_systems(OnEnter(MenuState::Inventory), inventory_screen_setup)
+        .add_systems(
+            OnExit(MenuState::Inventory),
+            (despawn_screen::<OnInventoryScreen>, capture_cursor),
+        )
+        .add_systems(OnExit(GameState::Game), reset_collected_pickups)
+        .add_systems(
+            Update,
+            (
+                collect_pickups,
+                name_pickups,
+                remove_collected_pickups,
+                update_slot_labels,
+            ),
+        )
+        .add_systems(
+            Update,
+            (open_inventory, select_hotbar_slot, update_hotbar_selection)
+                .chain()
+                .run_if(in_state(MenuState::Disabled)),
+        )
+        .add_systems(
+            Update,
+            (close_inventory, inventory_slot_button)
+                .in_set(MenuButtonSystems)
+                .run_if(in_state(MenuState::Inventory)),
+        );
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Most of the item a single slot holds
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+}
+
+fn default_stack_size() -> u32 {
+    1
+}
+
+// Item definitions by id, loaded from a `*.items.json` file
+#[derive(Asset, TypePath, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct ItemCatalog {
+    pub items: BTreeMap<String, ItemDefinition>,
+}
+
+#[derive(Default)]
+struct ItemCatalogLoader;
+
+impl AssetLoader for ItemCatalogLoader {
+    type Asset = ItemCatalog;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["items.json"]
+    }
+}
+
+// The catalog of every item in the game
+#[derive(Resource, Debug)]
+pub struct Items(pub Handle<ItemCatalog>);
+
+impl Items {
+    // None while the catalog is loading, or if no item has that id
+    pub fn get<'a>(
+        &self,
+        catalogs: &'a Assets<ItemCatalog>,
+        id: &str,
+    ) -> Option<&'a ItemDefinition> {
+        catalogs.get(&self.0)?.items.get(id)
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ItemStack {
+    // Id of the item definition
+    pub item: String,
+    pub count: u32,
+}
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug)]
+#[reflect(Component)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    // Index of the hotbar slot in hand
+    pub selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; INVENTORY_SIZE],
+            selected: 0,
+        }
+    }
+}
+
+impl Inventory {
+    // Tops up the stacks of the item first, then fills empty slots, returns how many didn't fit
+    pub fn add(&mut self, item: &str, mut count: u32, stack_size: u32) -> u32 {
+        let stack_size = stack_size.max(1);
+        for stack in self.slots.iter_mut().flatten() {
+            if stack.item == item {
+                let added = count.min(stack_size.saturating_sub(stack.count));
+                stack.count += added;
+                count -= added;
+            }
+        }
+        for slot in &mut self.slots {
+            if count == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let added = count.min(stack_size);
+                *slot = Some(ItemStack {
+                    item: String::from(item),
+                    count: added,
+                });
+                count -= added;
+            }
+        }
+        count
+    }
+
+    // Takes that many of the item, or nothing if there aren't enough
+    pub fn remove(&mut self, item: &str, mut count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+        // From the last slots first, so the hotbar keeps its items the longest
+        for slot in self.slots.iter_mut().rev() {
+            if count == 0 {
+                break;
+            }
+            let Some(stack) = slot.as_mut().filter(|stack| stack.item == item) else {
+                continue;
+            };
+            let removed = count.min(stack.count);
+            stack.count -= removed;
+            count -= removed;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        true
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.count(item) > 0
+    }
+
+    pub fn selected_item(&self) -> Option<&ItemStack> {
+        self.slots.get(self.selected)?.as_ref()
+    }
+}
+
+// Names of the level's pickups that were taken, so they aren't there again when a save is loaded
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct CollectedPickups(pub Vec<String>);
+
+fn load_items(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Items(asset_server.load("items/core.items.json")));
+}
+
+// Whatever doesn't fit stays in the world
+fn collect_pickups(
+    mut commands: Commands,
+    mut events: EventReader<PickedUp>,
+    items: Res<Items>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    mut inventories: Query<&mut Inventory>,
+    mut pickups: Query<(&mut Pickup, Option<&Name>)>,
+    mut collected: ResMut<CollectedPickups>,
+) {
+    for event in events.read() {
+        let Ok(mut inventory) = inventories.get_mut(event.by) else {
+            continue;
+        };
+        let Some(definition) = items.get(&catalogs, &event.item) else {
+            warn!("Picked up {} which isn't a known item", event.item);
+            continue;
+        };
+        let left = inventory.add(&event.item, event.count, definition.stack_size);
+        let Ok((mut pickup, name)) = pickups.get_mut(event.pickup) else {
+            continue;
+        };
+        if left == 0 {
+            if let Some(name) = name {
+                collected.0.push(name.to_string());
+            }
+            commands.entity(event.pickup).despawn_recursive();
+        } else {
+            pickup.count = left;
+        }
+    }
+}
+
+// Pickups are spawned with the item id in their prompt, unless the level gave them one
+fn name_pickups(
+    items: Res<Items>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    mut pickups: Query<(&Pickup, &mut Interactable), Added<Pickup>>,
+) {
+    for (pickup, mut interactable) in &mut pickups {
+        if let Some(definition) = items.get(&catalogs, &pickup.item) {
+            if interactable.prompt == format!("Pick up {}", pickup.item) {
+                interactable.prompt = format!("Pick up {}", definition.name);
+            }
+        }
+    }
+}
+
+fn remove_collected_pickups(
+    mut commands: Commands,
+    collected: Res<CollectedPickups>,
+    pickups: Query<(Entity, &Name), Added<Pickup>>,
+) {
+    for (entity, name) in &pickups {
+        if collected
+            .0
+            .iter()
+            .any(|collected| collected == name.as_str())
+        {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn reset_collected_pickups(mut collected: ResMut<CollectedPickups>) {
+    collected.0.clear();
+}
+
+// Tag component used to tag entities added on the hotbar
+#[derive(Component)]
+struct OnHotbar;
+
+// Tag component used to tag entities added on the inventory screen
+#[derive(Component)]
+struct OnInventoryScreen;
+
+// Inventory slot shown by a hotbar or inventory screen slot, on both the slot and its text
+#[derive(Component, Clone, Copy)]
+struct SlotIndex(usize);
+
+// Text describing the hovered or last clicked item
+#[derive(Component)]
+struct ItemDescription;
+
+#[derive(Component)]
+struct CloseInventoryButton;
+
+fn slot_label(stack: Option<&ItemStack>, items: &Items, catalogs: &Assets<ItemCatalog>) -> String {
+    let Some(stack) = stack else {
+        return String::new();
+    };
+    let name = items
+        .get(catalogs, &stack.item)
+        .map_or(stack.item.as_str(), |definition| definition.name.as_str());
+    if stack.count > 1 {
+        format!("{name}\nx{}", stack.count)
+    } else {
+        String::from(name)
+    }
+}
+
+fn slot_text() -> impl Bundle {
+    (
+        Text::default(),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+    )
+}
+
+fn hotbar_setup(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::End,
+                justify_content: JustifyContent::Center,
+                padding: UiRect::bottom(Val::Px(10.)),
+                ..default()
+            },
+            OnHotbar,
+        ))
+        .with_children(|parent| {
+            for index in 0..HOTBAR_SIZE {
+                parent
+                    .spawn((
+                        Node {
+                            width: Val::Px(90.),
+                            height: Val::Px(60.),
+                            margin: UiRect::all(Val::Px(3.)),
+                            border: UiRect::all(Val::Px(2.)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(NORMAL_BUTTON.with_alpha(0.7)),
+                        BorderColor(SLOT_BORDER),
+                        SlotIndex(index),
+                    ))
+                    .with_child((slot_text(), SlotIndex(index)));
+            }
+        });
+}
+
+fn update_slot_labels(
+    items: Res<Items>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    inventory: Single<&Inventory, With<Player>>,
+    mut texts: Query<(&mut Text, &SlotIndex)>,
+) {
+    for (mut text, index) in &mut texts {
+        let stack = inventory.slots.get(index.0).and_then(Option::as_ref);
+        let label = slot_label(stack, &items, &catalogs);
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
+
+fn select_hotbar_slot(
+    actions: Res<ActionState>,
+    mut inventory: Single<&mut Inventory, With<Player>>,
+) {
+    let step = actions.just_pressed(Action::NextItem) as i32
+        - actions.just_pressed(Action::PreviousItem) as i32;
+    if step != 0 {
+        inventory.selected =
+            (inventory.selected as i32 + step).rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+}
+
+fn update_hotbar_selection(
+    inventory: Single<&Inventory, With<Player>>,
+    mut slots: Query<(&mut BorderColor, &SlotIndex), Without<Text>>,
+) {
+    for (mut border_color, index) in &mut slots {
+        let color = if index.0 == inventory.selected {
+            SELECTED_SLOT_BORDER
+        } else {
+            SLOT_BORDER
+        };
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
+    }
+}
+
+fn open_inventory(
+    actions: Res<ActionState>,
+    mut window: Single<&mut Window>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.just_pressed(Action::Inventory) {
+        window.cursor_options.grab_mode = CursorGrabMode::None;
+        window.cursor_options.visible = true;
+        menu_state.set(MenuState::Inventory);
+    }
+}
+
+fn inventory_screen_setup(mut commands: Commands) {
+    let button_text_style = (
+        TextFont {
+            font_size: 33.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.6)),
+            OnInventoryScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Inventory"),
+                TextFont {
+                    font_size: 50.0,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+            ));
+
+            // One row per hotbar worth of slots, the hotbar set apart from the rest
+            for row in 0..INVENTORY_SIZE / HOTBAR_SIZE {
+                parent
+                    .spawn((Node {
+                        margin: UiRect::bottom(Val::Px(if row == 0 { 20. } else { 0. })),
+                        ..default()
+                    },))
+                    .with_children(|parent| {
+                        for column in 0..HOTBAR_SIZE {
+                            let index = row * HOTBAR_SIZE + column;
+                            parent
+                                .spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(110.),
+                                        height: Val::Px(70.),
+                                        margin: UiRect::all(Val::Px(4.)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(NORMAL_BUTTON),
+                                    SlotIndex(index),
+                                ))
+                                .with_child((slot_text(), SlotIndex(index)));
+                        }
+                    });
+            }
+
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+                Node {
+                    height: Val::Px(50.),
+                    margin: UiRect::vertical(Val::Px(20.)),
+                    ..default()
+                },
+                ItemDescription,
+            ));
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(200.0),
+                        height: Val::Px(65.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(NORMAL_BUTTON),
+                    CloseInventoryButton,
+                ))
+                .with_child((Text::new("Close"), button_text_style));
+        });
+}
+
+fn close_inventory(
+    actions: Res<ActionState>,
+    close_buttons: Query<&Interaction, (Changed<Interaction>, With<CloseInventoryButton>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.just_pressed(Action::Inventory)
+        || actions.just_pressed(Action::Pause)
+        || close_buttons
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        menu_state.set(MenuState::Disabled);
+    }
+}
+
+// Hovering a slot describes its item, clicking two slots one after the other swaps them
+fn inventory_slot_button(
+    mut commands: Commands,
+    items: Res<Items>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    interactions: Query<(Entity, &Interaction, &SlotIndex), (Changed<Interaction>, With<Button>)>,
+    mut selected: Query<(Entity, &SlotIndex, &mut BackgroundColor), With<SelectedOption>>,
+    mut inventory: Single<&mut Inventory, With<Player>>,
+    mut description: Single<&mut Text, With<ItemDescription>>,
+) {
+    for (entity, interaction, index) in &interactions {
+        if *interaction == Interaction::None {
+            continue;
+        }
+        description.0 = inventory
+            .slots
+            .get(index.0)
+            .and_then(Option::as_ref)
+            .and_then(|stack| items.get(&catalogs, &stack.item))
+            .map_or_else(String::new, |definition| {
+                format!("{}: {}", definition.name, definition.description)
+            });
+
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Ok((previous, previous_index, mut background_color)) = selected.get_single_mut() {
+            inventory.slots.swap(previous_index.0, index.0);
+            *background_color = NORMAL_BUTTON.into();
+            commands.entity(previous).remove::<SelectedOption>();
+        } else if inventory.slots.get(index.0).is_some_and(Option::is_some) {
+            commands.entity(entity).insert(SelectedOption);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::game::{on_scene_spawn, OnGameScreen};
+use crate::GameState;
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+use wave::*;
+
+// Width and depth of a level piece
+pub const CELL_SIZE: f32 = 2.;
+
+// Which level is spawned when entering `GameState::Game`
+#[derive(Resource, Reflect, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LevelSource {
+    // The hand made level of `untitled_game.glb`
+    #[default]
+    Fixed,
+    // A grid of pieces from `level_pieces.glb` laid out by the wave solver
+    Generated,
+}
+
+// Seed of the generated level, set to the seed used once it is generated so it can be saved
+// None picks a random seed, unless `LevelConfig` has one
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct LevelSeed(pub Option<u64>);
+
+#[derive(Resource, Debug, Clone)]
+pub struct LevelConfig {
+    pub width: usize,
+    pub height: usize,
+    // The same seed always generates the same level
+    pub seed: Option<u64>,
+    pub max_attempts: usize,
+}
+
+impl Default for LevelConfig {
+    fn default() -> Self {
+        Self {
+            width: 12,
+            height: 12,
+            seed: None,
+            max_attempts: 100,
+        }
+    }
+}
+
+// Edges are read clockwise starting with the north (-z) edge, L is floor height and H is block height
+struct LevelPiece {
+    scene: usize,
+    edges: [&'static str; 4],
+    rotations: usize,
+    weight: f32,
+}
+
+const LEVEL_PIECES: [LevelPiece; 3] = [
+    LevelPiece {
+        scene: 0,
+        edges: ["LLL", "LLL", "LLL", "LLL"],
+        rotations: 1,
+        weight: 3.,
+    },
+    LevelPiece {
+        scene: 1,
+        edges: ["HHH", "HHH", "HHH", "HHH"],
+        rotations: 1,
+        weight: 1.,
+    },
+    // Goes up toward the north edge
+    LevelPiece {
+        scene: 2,
+        edges: ["HHH", "HML", "LLL", "LMH"],
+        rotations: 4,
+        weight: 0.3,
+    },
+];
+
+#[derive(Resource)]
+pub struct LevelTileset {
+    pub tiles: Vec<Tile>,
+    // Indexed the same way as `tiles`
+    pub scenes: Vec<Handle<Scene>>,
+}
+
+pub fn level_plugin(app: &mut App) {
+    app.init_resource::<LevelSource>()
+        .init_resource::<LevelConfig>()
+        .init_resource::<LevelSeed>()
+        .add_systems(Startup, load_tileset)
+        .add_systems(
+            OnEnter(GameState::Game),
+            generate_level.run_if(resource_equals(LevelSource::Generated)),
+        )
+        .add_systems(OnExit(GameState::Game), reset_level_seed);
+}
+
+// The next random level is a new one, unless a save sets its seed
+fn reset_level_seed(mut level_seed: ResMut<LevelSeed>) {
+    level_seed.0 = None;
+}
+
+fn load_tileset(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut tiles = Vec::new();
+    let mut scenes = Vec::new();
+    for piece in LEVEL_PIECES {
+        let scene =
+            asset_server.load(GltfAssetLabel::Scene(piece.scene).from_asset("level_pieces.glb"));
+        let mut tile = Tile::new(Handle::default(), piece.edges.map(String::from).to_vec());
+        tile.set_weight(piece.weight);
+        for i in 0..piece.rotations {
+            tiles.push(tile.rotate(i));
+            scenes.push(scene.clone());
+        }
+    }
+
+    let tiles_ref = &tiles.clone();
+    for tile in &mut tiles {
+        tile.generate_relationships(tiles_ref)
+    }
+    commands.insert_resource(LevelTileset { tiles, scenes });
+}
+
+fn generate_level(
+    mut commands: Commands,
+    tileset: Res<LevelTileset>,
+    config: Res<LevelConfig>,
+    mut level_seed: ResMut<LevelSeed>,
+) {
+    if config.width == 0 || config.height == 0 {
+        error!(
+            "Can't generate a {}x{} level, it needs at least one piece",
+            config.width, config.height
+        );
+        return;
+    }
+    let seed = config
+        .seed
+        .or(level_seed.0)
+        .unwrap_or_else(|| rand::thread_rng().gen());
+    level_seed.0 = Some(seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut wave = Wave::new(
+        config.width,
+        config.height,
+        tileset.tiles.len(),
+        get_neighbors_no_wrap,
+    );
+    let solved = wave.solve(&tileset.tiles, &mut rng, config.max_attempts);
+    let stats = wave.stats;
+    if !solved {
+        error!(
+            "Failed to generate a level after {} attempts, {} contradictions in {:?}",
+            config.max_attempts, stats.contradictions, stats.elapsed
+        );
+        return;
+    }
+    info!(
+        "Generated level {seed} in {:?} over {} attempt(s), {} contradictions",
+        stats.elapsed,
+        stats.attempts(),
+        stats.contradictions
+    );
+
+    // Center the level on the origin, the grid's up is the world's north (-z)
+    let half_width = (config.width - 1) as f32 * CELL_SIZE / 2.;
+    let half_height = (config.height - 1) as f32 * CELL_SIZE / 2.;
+    commands
+        .spawn((
+            Name::new("Generated Level"),
+            Transform::default(),
+            Visibility::default(),
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            // The tiles don't have lights of their own like the fixed level
+            parent.spawn((
+                Name::new("Light"),
+                PointLight {
+                    shadows_enabled: true,
+                    ..default()
+                },
+                Transform::from_xyz(4.0, 8.0, 4.0),
+            ));
+            for coord in wave.coords() {
+                let tile = wave.cell(coord).options[0];
+                parent
+                    .spawn((
+                        SceneRoot(tileset.scenes[tile].clone()),
+                        Transform::from_xyz(
+                            coord.x as f32 * CELL_SIZE - half_width,
+                            0.,
+                            half_height - coord.y as f32 * CELL_SIZE,
+                        )
+                        // Tile rotations are clockwise when looking down on the level
+                        .with_rotation(Quat::from_rotation_y(
+                            -FRAC_PI_2 * tileset.tiles[tile].rotation as f32,
+                        )),
+                    ))
+                    .observe(on_scene_spawn);
+            }
+        });
+}
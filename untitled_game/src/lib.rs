@@ -1,9 +1,25 @@
 mod crs;
 
 pub use crs::*;
+pub mod audio;
+pub mod bounds;
 pub mod cameras;
+pub mod character;
+pub mod extras;
 pub mod game;
+pub mod gamepad;
+pub mod grab;
+pub mod graphics;
+pub mod input;
+pub mod interaction;
+pub mod inventory;
+pub mod level;
 pub mod menu;
+pub mod save;
+pub mod settings;
+pub mod spawner;
+pub mod storage;
+pub mod triggers;
 
 use avian3d::prelude::*;
 use bevy::prelude::*;
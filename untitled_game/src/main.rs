@@ -2,8 +2,11 @@ use avian3d::prelude::*;
 use bevy::core_pipeline::Skybox;
 use bevy::prelude::*;
 use bevy::render::render_resource::{TextureViewDescriptor, TextureViewDimension};
-use untitled_game::cameras::third_person;
-use untitled_game::{game, menu, Cubemap, DisplayQuality, GameState, PlayerState, Volume};
+use untitled_game::cameras::{self, first_person, freecam, third_person};
+use untitled_game::{
+    audio, bounds, character, extras, game, gamepad, grab, graphics, input, interaction, inventory,
+    level, menu, save, settings, spawner, triggers, Cubemap, GameState, PlayerState,
+};
 
 fn main() {
     let mut app = App::new();
@@ -27,9 +30,8 @@ fn main() {
     // show vertices normals
     app.add_plugins(PhysicsDebugPlugin::default());
 
-    // Insert as resource the initial value for the settings resources
-    app.insert_resource(DisplayQuality::Medium)
-        .insert_resource(Volume(7))
+    // Insert the saved settings as resources first, the other plugins read them
+    app.add_plugins(settings::settings_plugin)
         // Declare the game state, whose starting value is determined by the `Default` trait
         .init_state::<GameState>()
         .init_state::<PlayerState>()
@@ -38,9 +40,22 @@ fn main() {
         // Adds the plugins for each state
         .add_plugins((
             menu::menu_plugin,
+            audio::audio_plugin,
             game::game_plugin,
-            third_person::CameraControllerPlugin,
+            level::level_plugin,
+            character::character_plugin,
+            gamepad::gamepad_plugin,
+            graphics::graphics_plugin,
+            input::input_plugin,
+            interaction::interaction_plugin,
+            inventory::inventory_plugin,
+            grab::grab_plugin,
+            cameras::cameras_plugin,
+            save::save_plugin,
+            spawner::spawner_plugin,
+            bounds::bounds_plugin,
         ))
+        .add_plugins((extras::extras_plugin, triggers::triggers_plugin))
         .run();
 }
 
@@ -57,6 +72,8 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         Camera3d::default(),
         Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
         third_person::CameraController::default(),
+        first_person::CameraController::default(),
+        freecam::CameraController::default(),
         Skybox {
             image: skybox,
             brightness: 1000.,
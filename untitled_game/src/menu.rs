@@ -1,14 +1,22 @@
 use bevy::window::CursorGrabMode;
 use bevy::{app::AppExit, prelude::*};
 
-const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+pub(crate) const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 
-use crate::{despawn_screen, not_in_state, DisplayQuality, GameState, Volume};
+use crate::audio::{PlaySound, Sound};
+use crate::input::{Action, Binding, BindingSlot, InputMap};
+use crate::level::LevelSource;
+use crate::save::{LoadGame, SaveGame, SaveIndex, SAVE_SLOTS};
+use crate::{
+    despawn_screen, not_in_state, DisplayQuality, GameState, MusicVolume, SfxVolume, Volume,
+};
 
 // This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
-// - a settings menu with two submenus and a back button
-// - two settings screen with a setting that can be set and a back button
+// - a main menu with "Continue", "New Game", "Random Level", "Load Game", "Settings", "Quit",
+//   or "Resume" and "Save Game" instead of the first three during the game
+// - a settings screen with the settings that can be set, "Controls" and a back button
+// - a controls screen to rebind every action for keyboard & mouse and gamepad
+// - save and load screens listing the save slots
 pub fn menu_plugin(app: &mut App) {
     app
         // At start, the menu is not enabled. This will be changed in `menu_setup` when
@@ -23,17 +31,50 @@ pub fn menu_plugin(app: &mut App) {
         .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
         .add_systems(
             Update,
-            (setting_button::<Volume>, setting_button::<DisplayQuality>)
+            (
+                setting_button::<Volume>,
+                setting_button::<MusicVolume>,
+                setting_button::<SfxVolume>,
+                setting_button::<DisplayQuality>,
+            )
+                .in_set(MenuButtonSystems)
                 .run_if(in_state(MenuState::Settings)),
         )
         .add_systems(
             OnExit(MenuState::Settings),
             despawn_screen::<OnSettingsMenuScreen>,
         )
+        // Systems to handle the controls menu screen
+        .init_resource::<Rebinding>()
+        .add_systems(OnEnter(MenuState::Controls), controls_menu_setup)
+        .add_systems(
+            Update,
+            (start_rebinding, capture_binding, update_binding_labels)
+                .chain()
+                .in_set(MenuButtonSystems)
+                .run_if(in_state(MenuState::Controls)),
+        )
+        .add_systems(
+            OnExit(MenuState::Controls),
+            (despawn_screen::<OnControlsMenuScreen>, cancel_rebinding),
+        )
+        // Systems to handle the save and load screens, which only differ by what a slot does
+        .add_systems(OnEnter(MenuState::Save), save_slots_setup)
+        .add_systems(OnEnter(MenuState::Load), save_slots_setup)
+        .add_systems(
+            Update,
+            save_slot_action
+                .in_set(MenuButtonSystems)
+                .run_if(in_state(MenuState::Save).or(in_state(MenuState::Load))),
+        )
+        .add_systems(OnExit(MenuState::Save), despawn_screen::<OnSaveSlotsScreen>)
+        .add_systems(OnExit(MenuState::Load), despawn_screen::<OnSaveSlotsScreen>)
         // Common systems to all screens that handles buttons behavior
         .add_systems(
             Update,
-            (menu_action, button_system).run_if(not_in_state(MenuState::Disabled)),
+            (menu_action, button_system)
+                .in_set(MenuButtonSystems)
+                .run_if(not_in_state(MenuState::Disabled)),
         );
 }
 
@@ -42,10 +83,19 @@ pub fn menu_plugin(app: &mut App) {
 pub enum MenuState {
     Main,
     Settings,
+    Controls,
+    Save,
+    Load,
+    // The inventory screen, opened during the game
+    Inventory,
     #[default]
     Disabled,
 }
 
+// Systems reacting to the menu buttons' `Interaction`, whatever screen they are on
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MenuButtonSystems;
+
 // Tag component used to tag entities added on the main menu screen
 #[derive(Component)]
 struct OnMainMenuScreen;
@@ -54,32 +104,66 @@ struct OnMainMenuScreen;
 #[derive(Component)]
 struct OnSettingsMenuScreen;
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+// Tag component used to tag entities added on the controls menu screen
+#[derive(Component)]
+struct OnControlsMenuScreen;
+
+// Tag component used to tag entities added on the save and load screens
+#[derive(Component)]
+struct OnSaveSlotsScreen;
+
+// Button saving to, or loading from, a save slot
+#[derive(Component)]
+struct SaveSlotButton(usize);
+
+// The action and slot waiting for the player to press the new binding
+#[derive(Resource, Default)]
+pub struct Rebinding(pub Option<(Action, BindingSlot)>);
+
+// Button, and the text inside it, showing the binding of an action in a slot
+#[derive(Component, Clone, Copy)]
+struct BindingButton {
+    action: Action,
+    slot: BindingSlot,
+}
+
+pub(crate) const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::srgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
 
 // Tag component used to mark which setting is currently selected
 #[derive(Component)]
-struct SelectedOption;
+pub(crate) struct SelectedOption;
 
 // All actions that can be triggered from a button click
 #[derive(Component)]
 enum MenuButtonAction {
+    Continue,
     Play,
+    PlayGenerated,
+    SaveGame,
+    LoadGame,
     Settings,
+    Controls,
+    ResetBindings,
     BackToMainMenu,
+    BackToSettings,
     Quit,
 }
 
-// This system handles changing all buttons color based on mouse interaction
+// This system handles changing all buttons color based on mouse interaction, and clicking
 fn button_system(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
         (Changed<Interaction>, With<Button>),
     >,
+    mut sounds: EventWriter<PlaySound>,
 ) {
     for (interaction, mut background_color, selected) in &mut interaction_query {
+        if *interaction == Interaction::Pressed {
+            sounds.send(PlaySound::new(Sound::Click));
+        }
         *background_color = match (*interaction, selected) {
             (Interaction::Pressed, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
             (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
@@ -112,12 +196,16 @@ fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
     menu_state.set(MenuState::Main);
 }
 
-fn main_menu_setup(mut commands: Commands, game_state: Res<State<GameState>>) {
+fn main_menu_setup(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    save_index: Res<SaveIndex>,
+) {
     // Common style for all buttons on the screen
     let button_node = Node {
         width: Val::Px(300.0),
         height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
+        margin: UiRect::all(Val::Px(12.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -127,6 +215,31 @@ fn main_menu_setup(mut commands: Commands, game_state: Res<State<GameState>>) {
         ..default()
     };
 
+    // The buttons for each action available from the main menu:
+    // - continue the last save, new game and random level, only outside of a game
+    // - resume and save game, only during a game
+    // - load game, when there are saves
+    // - settings
+    // - quit
+    let in_game = game_state.get() == &GameState::Game;
+    let mut buttons = Vec::new();
+    if in_game {
+        buttons.push((MenuButtonAction::Play, "Resume"));
+        buttons.push((MenuButtonAction::SaveGame, "Save Game"));
+    } else {
+        if save_index.last.is_some() {
+            buttons.push((MenuButtonAction::Continue, "Continue"));
+        }
+        buttons.push((MenuButtonAction::Play, "New Game"));
+        buttons.push((MenuButtonAction::PlayGenerated, "Random Level"));
+    }
+    if !save_index.slots.is_empty() {
+        buttons.push((MenuButtonAction::LoadGame, "Load Game"));
+    }
+    buttons.push((MenuButtonAction::Settings, "Settings"));
+    #[cfg(not(target_family = "wasm"))]
+    buttons.push((MenuButtonAction::Quit, "Quit"));
+
     commands
         .spawn((
             Node {
@@ -156,66 +269,137 @@ fn main_menu_setup(mut commands: Commands, game_state: Res<State<GameState>>) {
                         TextColor(TEXT_COLOR),
                     ));
 
-                    // Display three buttons for each action available from the main menu:
-                    // - new game
-                    // - settings
-                    // - quit
-                    parent
-                        .spawn((
-                            Button,
-                            button_node.clone(),
-                            BackgroundColor(NORMAL_BUTTON),
-                            MenuButtonAction::Play,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn((
-                                Text::new(if game_state.get() == &GameState::Game {
-                                    "Resume"
-                                } else {
-                                    "New Game"
-                                }),
-                                button_text_font.clone(),
-                                TextColor(TEXT_COLOR),
-                            ));
-                        });
-                    parent
-                        .spawn((
-                            Button,
-                            button_node.clone(),
-                            BackgroundColor(NORMAL_BUTTON),
-                            MenuButtonAction::Settings,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn((
-                                Text::new("Settings"),
-                                button_text_font.clone(),
-                                TextColor(TEXT_COLOR),
-                            ));
+                    for (action, label) in buttons {
+                        parent
+                            .spawn((
+                                Button,
+                                button_node.clone(),
+                                BackgroundColor(NORMAL_BUTTON),
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(label),
+                                    button_text_font.clone(),
+                                    TextColor(TEXT_COLOR),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+fn save_slots_setup(
+    mut commands: Commands,
+    menu_state: Res<State<MenuState>>,
+    save_index: Res<SaveIndex>,
+) {
+    let button_node = Node {
+        width: Val::Px(500.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(12.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = (
+        TextFont {
+            font_size: 33.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+    let saving = menu_state.get() == &MenuState::Save;
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Start,
+                ..default()
+            },
+            OnSaveSlotsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Start,
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(if saving { "Save Game" } else { "Load Game" }),
+                        TextFont {
+                            font_size: 67.0,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ));
+
+                    for slot in 0..SAVE_SLOTS {
+                        let summary = save_index.slots.get(&slot);
+                        let label = format!(
+                            "Slot {}: {}",
+                            slot + 1,
+                            summary.map_or("Empty", String::as_str)
+                        );
+                        let mut entity =
+                            parent.spawn((button_node.clone(), BackgroundColor(NORMAL_BUTTON)));
+                        entity.with_children(|parent| {
+                            parent.spawn((Text::new(label), button_text_style.clone()));
                         });
+                        // There is nothing to load from an empty slot
+                        if saving || summary.is_some() {
+                            entity.insert((Button, SaveSlotButton(slot)));
+                        }
+                    }
 
-                    #[cfg(not(target_family = "wasm"))]
                     parent
                         .spawn((
                             Button,
-                            button_node,
+                            Node {
+                                width: Val::Px(200.0),
+                                ..button_node
+                            },
                             BackgroundColor(NORMAL_BUTTON),
-                            MenuButtonAction::Quit,
+                            MenuButtonAction::BackToMainMenu,
                         ))
                         .with_children(|parent| {
-                            parent.spawn((
-                                Text::new("Quit"),
-                                button_text_font,
-                                TextColor(TEXT_COLOR),
-                            ));
+                            parent.spawn((Text::new("Back"), button_text_style));
                         });
                 });
         });
 }
 
+fn save_slot_action(
+    interaction_query: Query<(&Interaction, &SaveSlotButton), (Changed<Interaction>, With<Button>)>,
+    menu_state: Res<State<MenuState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut save_events: EventWriter<SaveGame>,
+    mut load_events: EventWriter<LoadGame>,
+) {
+    for (interaction, SaveSlotButton(slot)) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            if menu_state.get() == &MenuState::Save {
+                save_events.send(SaveGame(*slot));
+                next_menu_state.set(MenuState::Main);
+            } else {
+                // Loading leaves the menu once the save is read
+                load_events.send(LoadGame(*slot));
+            }
+        }
+    }
+}
+
 fn settings_menu_setup(
     mut commands: Commands,
     display_quality: Res<DisplayQuality>,
     volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    sfx_volume: Res<SfxVolume>,
 ) {
     let button_node = Node {
         width: Val::Px(200.0),
@@ -291,28 +475,40 @@ fn settings_menu_setup(
                             }
                         });
 
+                    spawn_volume_row(
+                        parent,
+                        "Volume",
+                        *volume,
+                        Volume,
+                        &button_node,
+                        &button_text_style,
+                    );
+                    spawn_volume_row(
+                        parent,
+                        "Music",
+                        *music_volume,
+                        MusicVolume,
+                        &button_node,
+                        &button_text_style,
+                    );
+                    spawn_volume_row(
+                        parent,
+                        "Effects",
+                        *sfx_volume,
+                        SfxVolume,
+                        &button_node,
+                        &button_text_style,
+                    );
+
                     parent
-                        .spawn((Node {
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },))
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            MenuButtonAction::Controls,
+                        ))
                         .with_children(|parent| {
-                            parent.spawn((Text::new("Volume"), button_text_style.clone()));
-                            for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
-                                let mut entity = parent.spawn((
-                                    Button,
-                                    Node {
-                                        width: Val::Px(30.0),
-                                        height: Val::Px(65.0),
-                                        ..button_node.clone()
-                                    },
-                                    BackgroundColor(NORMAL_BUTTON),
-                                    Volume(volume_setting),
-                                ));
-                                if *volume == Volume(volume_setting) {
-                                    entity.insert(SelectedOption);
-                                }
-                            }
+                            parent.spawn((Text::new("Controls"), button_text_style.clone()));
                         });
 
                     parent
@@ -329,6 +525,251 @@ fn settings_menu_setup(
         });
 }
 
+// A label and a button for each volume level from 0 to 9
+fn spawn_volume_row<T: Component + PartialEq>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    current: T,
+    setting: impl Fn(u32) -> T,
+    button_node: &Node,
+    text_style: &(TextFont, TextColor),
+) {
+    parent
+        .spawn((Node {
+            align_items: AlignItems::Center,
+            ..default()
+        },))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                text_style.clone(),
+                // Keeps the buttons of every row aligned
+                Node {
+                    width: Val::Px(150.0),
+                    ..default()
+                },
+            ));
+            for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
+                let mut entity = parent.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(30.0),
+                        height: Val::Px(50.0),
+                        margin: UiRect::all(Val::Px(10.0)),
+                        ..button_node.clone()
+                    },
+                    BackgroundColor(NORMAL_BUTTON),
+                    setting(volume_setting),
+                ));
+                if current == setting(volume_setting) {
+                    entity.insert(SelectedOption);
+                }
+            }
+        });
+}
+
+fn controls_menu_setup(mut commands: Commands, input_map: Res<InputMap>) {
+    let button_node = Node {
+        width: Val::Px(200.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    // There are a lot of actions, the rows are smaller than the other screens' buttons
+    let binding_node = Node {
+        width: Val::Px(250.0),
+        height: Val::Px(32.0),
+        margin: UiRect::axes(Val::Px(10.0), Val::Px(1.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let label_node = Node {
+        width: Val::Px(220.0),
+        ..default()
+    };
+
+    let button_text_style = (
+        TextFont {
+            font_size: 33.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+    let binding_text_style = (
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Start,
+                ..default()
+            },
+            OnControlsMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Start,
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    // One row per action, with a column for each binding slot
+                    for action in Action::ALL {
+                        parent
+                            .spawn((Node {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(action.name()),
+                                    binding_text_style.clone(),
+                                    label_node.clone(),
+                                ));
+                                for slot in [BindingSlot::KeyboardMouse, BindingSlot::Gamepad] {
+                                    let binding_button = BindingButton { action, slot };
+                                    parent
+                                        .spawn((
+                                            Button,
+                                            binding_node.clone(),
+                                            BackgroundColor(NORMAL_BUTTON),
+                                            binding_button,
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                Text::new(binding_label(
+                                                    input_map.binding(action, slot),
+                                                )),
+                                                binding_text_style.clone(),
+                                                binding_button,
+                                            ));
+                                        });
+                                }
+                            });
+                    }
+
+                    parent
+                        .spawn((Node {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },))
+                        .with_children(|parent| {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button_node.clone(),
+                                    BackgroundColor(NORMAL_BUTTON),
+                                    MenuButtonAction::ResetBindings,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((Text::new("Reset"), button_text_style.clone()));
+                                });
+                            parent
+                                .spawn((
+                                    Button,
+                                    button_node.clone(),
+                                    BackgroundColor(NORMAL_BUTTON),
+                                    MenuButtonAction::BackToSettings,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((Text::new("Back"), button_text_style.clone()));
+                                });
+                        });
+                });
+        });
+}
+
+fn binding_label(binding: Option<Binding>) -> String {
+    binding.map_or_else(|| String::from("-"), |binding| binding.label())
+}
+
+fn start_rebinding(
+    interaction_query: Query<(&Interaction, &BindingButton), (Changed<Interaction>, With<Button>)>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (interaction, binding_button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some((binding_button.action, binding_button.slot));
+        }
+    }
+}
+
+// Binds the next key, mouse button or gamepad button pressed, Escape cancels
+fn capture_binding(
+    key_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+) {
+    // The click that started rebinding isn't the new binding
+    if rebinding.is_changed() {
+        return;
+    }
+    let Some((action, slot)) = rebinding.0 else {
+        return;
+    };
+    if key_input.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+
+    let binding = match slot {
+        BindingSlot::KeyboardMouse => key_input
+            .get_just_pressed()
+            .next()
+            .map(|key| Binding::Key(*key))
+            .or_else(|| {
+                mouse_input
+                    .get_just_pressed()
+                    .next()
+                    .map(|button| Binding::Mouse(*button))
+            }),
+        BindingSlot::Gamepad => gamepads
+            .iter()
+            .find_map(|gamepad| gamepad.get_just_pressed().next())
+            .map(|button| Binding::Gamepad(*button)),
+    };
+    if let Some(binding) = binding {
+        input_map.rebind(action, binding);
+        rebinding.0 = None;
+    }
+}
+
+fn update_binding_labels(
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    mut labels: Query<(&mut Text, &BindingButton)>,
+) {
+    if !input_map.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (mut text, binding_button) in &mut labels {
+        text.0 = if rebinding.0 == Some((binding_button.action, binding_button.slot)) {
+            String::from("Press a button...")
+        } else {
+            binding_label(input_map.binding(binding_button.action, binding_button.slot))
+        };
+    }
+}
+
+fn cancel_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+#[allow(clippy::too_many_arguments)]
 fn menu_action(
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
@@ -339,6 +780,10 @@ fn menu_action(
     game_state: Res<State<GameState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut window: Single<&mut Window>,
+    mut level_source: ResMut<LevelSource>,
+    mut input_map: ResMut<InputMap>,
+    save_index: Res<SaveIndex>,
+    mut load_events: EventWriter<LoadGame>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -346,16 +791,30 @@ fn menu_action(
                 MenuButtonAction::Quit => {
                     app_exit_events.send(AppExit::Success);
                 }
-                MenuButtonAction::Play => {
+                MenuButtonAction::Play | MenuButtonAction::PlayGenerated => {
                     if game_state.get() != &GameState::Game {
+                        *level_source = match menu_button_action {
+                            MenuButtonAction::PlayGenerated => LevelSource::Generated,
+                            _ => LevelSource::Fixed,
+                        };
                         next_game_state.set(GameState::Game);
                     }
                     window.cursor_options.grab_mode = CursorGrabMode::Locked;
                     window.cursor_options.visible = false;
                     next_menu_state.set(MenuState::Disabled);
                 }
+                MenuButtonAction::Continue => {
+                    if let Some(slot) = save_index.last {
+                        load_events.send(LoadGame(slot));
+                    }
+                }
+                MenuButtonAction::SaveGame => next_menu_state.set(MenuState::Save),
+                MenuButtonAction::LoadGame => next_menu_state.set(MenuState::Load),
                 MenuButtonAction::Settings => next_menu_state.set(MenuState::Settings),
+                MenuButtonAction::Controls => next_menu_state.set(MenuState::Controls),
+                MenuButtonAction::ResetBindings => *input_map = InputMap::default(),
                 MenuButtonAction::BackToMainMenu => next_menu_state.set(MenuState::Main),
+                MenuButtonAction::BackToSettings => next_menu_state.set(MenuState::Settings),
             }
         }
     }
@@ -0,0 +1,521 @@
+//! Save slots. A save is a `DynamicScene` of what it takes to rebuild the game, written as RON
+//! with the [`storage`] helpers: the player, the dynamic bodies, the level and the settings.
+//! The scene isn't spawned as is, it is written to a scratch world and read back from there.
+//! The game is then started like a new one and the saved state applied on top of it.
+
+use crate::bounds::{AwaitingSpawn, LastCheckpoint};
+use crate::extras::ExtrasReady;
+use crate::game::game_setup;
+use crate::inventory::{CollectedPickups, Inventory};
+use crate::level::{LevelSeed, LevelSource};
+use crate::menu::MenuState;
+use crate::settings::Settings;
+use crate::spawner::{spawn_scene, RestoredSpawned, Spawned};
+use crate::{
+    capture_cursor, storage, DisplayQuality, GameState, MusicVolume, Player, SfxVolume, Volume,
+};
+use avian3d::prelude::*;
+use bevy::ecs::entity::{EntityHashMap, EntityHashSet};
+use bevy::prelude::*;
+use bevy::scene::serde::SceneDeserializer;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const SAVE_SLOTS: usize = 3;
+const SAVE_INDEX_KEY: &str = "saves.json";
+
+pub fn save_plugin(app: &mut App) {
+    app.insert_resource(SaveIndex::load())
+        // Everything that goes in a save file
+        .register_type::<Player>()
+        .register_type::<Inventory>()
+        .register_type::<SavedBody>()
+        .register_type::<SavedLevel>()
+        .register_type::<CollectedPickups>()
+        .register_type::<LastCheckpoint>()
+        .register_type::<DisplayQuality>()
+        .register_type::<Volume>()
+        .register_type::<MusicVolume>()
+        .register_type::<SfxVolume>()
+        .add_event::<SaveGame>()
+        .add_event::<LoadGame>()
+        .add_observer(expire_pending_bodies)
+        // After the menu sent them, so the index is up to date when the next screen is built
+        .add_systems(
+            PostUpdate,
+            (
+                save_game.run_if(on_event::<SaveGame>),
+                load_game.run_if(on_event::<LoadGame>),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                start_pending_load
+                    .run_if(resource_exists::<PendingLoad>)
+                    .run_if(in_state(GameState::Menu)),
+                restore_bodies,
+            ),
+        )
+        .add_systems(
+            OnEnter(GameState::Game),
+            apply_pending_load
+                .after(game_setup)
+                .run_if(resource_exists::<PendingLoad>),
+        )
+        .add_systems(OnExit(GameState::Game), remove_pending_bodies);
+}
+
+// Saves the running game in the slot
+#[derive(Event, Debug)]
+pub struct SaveGame(pub usize);
+
+// Replaces the running game, if any, with the one saved in the slot
+#[derive(Event, Debug)]
+pub struct LoadGame(pub usize);
+
+// What the menu shows about the slots without reading every save
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct SaveIndex {
+    // Slot saved to last, the one "Continue" loads
+    pub last: Option<usize>,
+    // Short description of each slot in use
+    pub slots: BTreeMap<usize, String>,
+}
+
+impl SaveIndex {
+    pub fn load() -> Self {
+        let Some(contents) = storage::load(SAVE_INDEX_KEY) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring the save index: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(contents) => {
+                if let Err(err) = storage::save(SAVE_INDEX_KEY, &contents) {
+                    error!("Failed to save the save index: {err}");
+                }
+            }
+            Err(err) => error!("Failed to serialize the save index: {err}"),
+        }
+    }
+}
+
+fn slot_key(slot: usize) -> String {
+    format!("save_{slot}.scn.ron")
+}
+
+// A dynamic body, its `Transform` in the save is the global one
+#[derive(Component, Reflect, Default, Clone, Debug)]
+#[reflect(Component, Default)]
+struct SavedBody {
+    // Bodies of the level are found again by name
+    name: Option<String>,
+    // Bodies from a spawner are respawned from the asset path of their scene instead, and given
+    // back to the spawner with that name
+    scene: Option<String>,
+    spawner: Option<String>,
+    age: f32,
+    linear_velocity: Vec3,
+    angular_velocity: Vec3,
+}
+
+#[derive(Resource, Reflect, Default, Clone, Copy, Debug)]
+#[reflect(Resource, Default)]
+struct SavedLevel {
+    source: LevelSource,
+    // Only for generated levels
+    seed: Option<u64>,
+}
+
+fn summary(level: &SavedLevel, inventory: &Inventory) -> String {
+    let items = inventory
+        .slots
+        .iter()
+        .flatten()
+        .map(|stack| stack.count)
+        .sum::<u32>();
+    let level = match level.source {
+        LevelSource::Fixed => "Level One",
+        LevelSource::Generated => "Random Level",
+    };
+    format!("{level}, {items} items")
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_game(
+    mut events: EventReader<SaveGame>,
+    registry: Res<AppTypeRegistry>,
+    player: Single<(&Transform, &Inventory), With<Player>>,
+    bodies: Query<
+        (
+            Entity,
+            &RigidBody,
+            &GlobalTransform,
+            &LinearVelocity,
+            &AngularVelocity,
+            Option<&Name>,
+        ),
+        Without<Player>,
+    >,
+    parents: Query<&Parent>,
+    spawned: Query<(&Spawned, &SceneRoot)>,
+    names: Query<&Name>,
+    level: (
+        Res<LevelSource>,
+        Res<LevelSeed>,
+        Res<CollectedPickups>,
+        Res<LastCheckpoint>,
+    ),
+    settings: (
+        Res<DisplayQuality>,
+        Res<Volume>,
+        Res<MusicVolume>,
+        Res<SfxVolume>,
+    ),
+    mut index: ResMut<SaveIndex>,
+) {
+    let (transform, inventory) = *player;
+    let (level_source, level_seed, collected, last_checkpoint) = level;
+    let (display_quality, volume, music_volume, sfx_volume) = settings;
+    let saved_level = SavedLevel {
+        source: *level_source,
+        seed: level_seed.0,
+    };
+
+    for event in events.read() {
+        let mut world = World::new();
+        world.insert_resource(registry.clone());
+        world.spawn((Player, *transform, inventory.clone()));
+        for (entity, rigid_body, global_transform, linear_velocity, angular_velocity, name) in
+            &bodies
+        {
+            if !rigid_body.is_dynamic() {
+                continue;
+            }
+            let mut body = SavedBody {
+                linear_velocity: linear_velocity.0,
+                angular_velocity: angular_velocity.0,
+                ..default()
+            };
+            if let Some((spawned, scene_root)) = parents
+                .iter_ancestors(entity)
+                .find_map(|ancestor| spawned.get(ancestor).ok())
+            {
+                let Some(path) = scene_root.0.path() else {
+                    continue;
+                };
+                body.scene = Some(path.to_string());
+                body.spawner = names.get(spawned.spawner).ok().map(Name::to_string);
+                body.age = spawned.age;
+            } else if let Some(name) = name {
+                body.name = Some(name.to_string());
+            } else {
+                // There would be no way to find it again
+                continue;
+            }
+            world.spawn((global_transform.compute_transform(), body));
+        }
+        world.insert_resource(saved_level);
+        world.insert_resource(CollectedPickups(collected.0.clone()));
+        world.insert_resource(LastCheckpoint(last_checkpoint.0));
+        world.insert_resource(*display_quality);
+        world.insert_resource(*volume);
+        world.insert_resource(*music_volume);
+        world.insert_resource(*sfx_volume);
+
+        let contents = match DynamicScene::from_world(&world).serialize(&registry.read()) {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!("Failed to serialize the game: {err}");
+                continue;
+            }
+        };
+        if let Err(err) = storage::save(&slot_key(event.0), &contents) {
+            error!("Failed to save the game: {err}");
+            continue;
+        }
+        index.last = Some(event.0);
+        index
+            .slots
+            .insert(event.0, summary(&saved_level, inventory));
+        index.save();
+    }
+}
+
+// A save read back from its scene, applied when the game is started again
+#[derive(Resource)]
+struct PendingLoad {
+    player: Option<(Transform, Inventory)>,
+    bodies: Vec<(Transform, SavedBody)>,
+    level: SavedLevel,
+    collected: Vec<String>,
+    checkpoint: Option<Vec3>,
+    settings: Settings,
+}
+
+fn read_save(
+    contents: &str,
+    registry: &AppTypeRegistry,
+) -> Result<PendingLoad, Box<dyn std::error::Error>> {
+    let mut deserializer = ron::de::Deserializer::from_str(contents)?;
+    let scene = SceneDeserializer {
+        type_registry: &registry.read(),
+    }
+    .deserialize(&mut deserializer)?;
+    let mut world = World::new();
+    scene.write_to_world_with(&mut world, &mut EntityHashMap::default(), registry)?;
+
+    let player = world
+        .query_filtered::<(&Transform, &Inventory), With<Player>>()
+        .iter(&world)
+        .next()
+        .map(|(transform, inventory)| (*transform, inventory.clone()));
+    let bodies = world
+        .query::<(&Transform, &SavedBody)>()
+        .iter(&world)
+        .map(|(transform, body)| (*transform, body.clone()))
+        .collect();
+    // Saves missing a setting keep the current one
+    let defaults = Settings::load();
+    Ok(PendingLoad {
+        player,
+        bodies,
+        level: world
+            .get_resource::<SavedLevel>()
+            .copied()
+            .unwrap_or_default(),
+        collected: world
+            .get_resource::<CollectedPickups>()
+            .map(|collected| collected.0.clone())
+            .unwrap_or_default(),
+        checkpoint: world
+            .get_resource::<LastCheckpoint>()
+            .and_then(|checkpoint| checkpoint.0),
+        settings: Settings {
+            display_quality: world
+                .get_resource()
+                .copied()
+                .unwrap_or(defaults.display_quality),
+            volume: world.get_resource().copied().unwrap_or(defaults.volume),
+            music_volume: world
+                .get_resource()
+                .copied()
+                .unwrap_or(defaults.music_volume),
+            sfx_volume: world.get_resource().copied().unwrap_or(defaults.sfx_volume),
+        },
+    })
+}
+
+fn load_game(
+    mut commands: Commands,
+    mut events: EventReader<LoadGame>,
+    registry: Res<AppTypeRegistry>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let Some(LoadGame(slot)) = events.read().last() else {
+        return;
+    };
+    let Some(contents) = storage::load(&slot_key(*slot)) else {
+        error!("Save slot {} is empty", slot + 1);
+        return;
+    };
+    match read_save(&contents, &registry) {
+        Ok(pending) => commands.insert_resource(pending),
+        Err(err) => {
+            error!("Failed to load save slot {}: {err}", slot + 1);
+            return;
+        }
+    }
+    // Leaving the game despawns it, the load starts it again once in the menu
+    if *game_state.get() == GameState::Game {
+        next_game_state.set(GameState::Menu);
+    }
+}
+
+// Everything read while entering the game is set before it starts
+fn start_pending_load(
+    pending: Res<PendingLoad>,
+    mut level: (
+        ResMut<LevelSource>,
+        ResMut<LevelSeed>,
+        ResMut<CollectedPickups>,
+        ResMut<LastCheckpoint>,
+    ),
+    mut settings: (
+        ResMut<DisplayQuality>,
+        ResMut<Volume>,
+        ResMut<MusicVolume>,
+        ResMut<SfxVolume>,
+    ),
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    window: Single<&mut Window>,
+) {
+    *level.0 = pending.level.source;
+    level.1 .0 = pending.level.seed;
+    level.2 .0 = pending.collected.clone();
+    level.3 .0 = pending.checkpoint;
+    // Only set when different, so the settings aren't written back for nothing
+    settings.0.set_if_neq(pending.settings.display_quality);
+    settings.1.set_if_neq(pending.settings.volume);
+    settings.2.set_if_neq(pending.settings.music_volume);
+    settings.3.set_if_neq(pending.settings.sfx_volume);
+
+    next_game_state.set(GameState::Game);
+    next_menu_state.set(MenuState::Disabled);
+    capture_cursor(window);
+}
+
+// Velocity of a respawned body, given to it once its scene is spawned
+#[derive(Component)]
+struct RestoreVelocity {
+    linear: Vec3,
+    angular: Vec3,
+}
+
+// The level's bodies waiting for their scene to be spawned
+#[derive(Resource)]
+struct PendingBodies {
+    bodies: Vec<(Transform, SavedBody)>,
+    // Set once the level's scene is ready, the bodies it didn't have are dropped after that
+    level_ready: bool,
+}
+
+fn apply_pending_load(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pending: Res<PendingLoad>,
+    mut players: Query<(Entity, &mut Transform, &mut Inventory), With<Player>>,
+) {
+    commands.remove_resource::<PendingLoad>();
+    if let (
+        Some((transform, inventory)),
+        Ok((player, mut player_transform, mut player_inventory)),
+    ) = (&pending.player, players.get_single_mut())
+    {
+        *player_transform = *transform;
+        *player_inventory = inventory.clone();
+        // Already where it was saved, the level's spawn would move it back to the start
+        commands.entity(player).remove::<AwaitingSpawn>();
+    }
+
+    let mut level_bodies = Vec::new();
+    for (transform, body) in &pending.bodies {
+        let Some(scene) = &body.scene else {
+            level_bodies.push((*transform, body.clone()));
+            continue;
+        };
+        // The scale comes from the scene
+        let mut root = spawn_scene(
+            &mut commands,
+            asset_server.load(scene),
+            Transform::from_translation(transform.translation).with_rotation(transform.rotation),
+        );
+        root.insert(RestoreVelocity {
+            linear: body.linear_velocity,
+            angular: body.angular_velocity,
+        });
+        if let Some(spawner) = &body.spawner {
+            root.insert(RestoredSpawned {
+                spawner: spawner.clone(),
+                age: body.age,
+            });
+        }
+    }
+    if !level_bodies.is_empty() {
+        commands.insert_resource(PendingBodies {
+            bodies: level_bodies,
+            level_ready: false,
+        });
+    }
+}
+
+// The level's bodies are given their `RigidBody` when its scene is ready, the ones left after
+// that aren't in the level anymore
+fn expire_pending_bodies(
+    trigger: Trigger<ExtrasReady>,
+    pending: Option<ResMut<PendingBodies>>,
+    respawned: Query<(), Or<(With<RestoreVelocity>, With<Spawned>, With<RestoredSpawned>)>>,
+) {
+    if let Some(mut pending) = pending {
+        if !respawned.contains(trigger.entity()) {
+            pending.level_ready = true;
+        }
+    }
+}
+
+fn remove_pending_bodies(mut commands: Commands) {
+    commands.remove_resource::<PendingBodies>();
+}
+
+// Bodies get their `RigidBody` when their scene is spawned, that's when they can be restored
+fn restore_bodies(
+    mut commands: Commands,
+    mut pending: Option<ResMut<PendingBodies>>,
+    bodies: Query<(Entity, Option<&Name>, Option<&Parent>), Added<RigidBody>>,
+    parents: Query<&Parent>,
+    restore_velocities: Query<&RestoreVelocity>,
+    spawned: Query<(), Or<(With<Spawned>, With<RestoredSpawned>)>>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    // Bodies of the same scene are given their `RigidBody` together
+    let mut restored_roots = EntityHashSet::default();
+    for (entity, name, parent) in &bodies {
+        // Respawned bodies are already in place, only their velocity is missing
+        if let Some((root, velocity)) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| Some((ancestor, restore_velocities.get(ancestor).ok()?)))
+        {
+            commands.entity(entity).insert((
+                LinearVelocity(velocity.linear),
+                AngularVelocity(velocity.angular),
+            ));
+            restored_roots.insert(root);
+            continue;
+        }
+        // Newly spawned bodies can have the same name as the level's
+        if parents
+            .iter_ancestors(entity)
+            .any(|ancestor| spawned.contains(ancestor))
+        {
+            continue;
+        }
+
+        let (Some(pending), Some(name)) = (pending.as_mut(), name) else {
+            continue;
+        };
+        let Some(index) = pending
+            .bodies
+            .iter()
+            .position(|(_, body)| body.name.as_deref() == Some(name.as_str()))
+        else {
+            continue;
+        };
+        let (transform, body) = pending.bodies.swap_remove(index);
+        // The saved transform is global, the body's is relative to its parent
+        let transform = match parent.and_then(|parent| global_transforms.get(parent.get()).ok()) {
+            Some(parent) => GlobalTransform::from(transform).reparented_to(parent),
+            None => transform,
+        };
+        commands.entity(entity).insert((
+            transform,
+            LinearVelocity(body.linear_velocity),
+            AngularVelocity(body.angular_velocity),
+        ));
+    }
+    for root in restored_roots {
+        commands.entity(root).remove::<RestoreVelocity>();
+    }
+    if pending.is_some_and(|pending| pending.level_ready || pending.bodies.is_empty()) {
+        commands.remove_resource::<PendingBodies>();
+    }
+}
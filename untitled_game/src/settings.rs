@@ -0,0 +1,90 @@
+//! Saves the settings changed in the menu and restores them on the next run.
+
+use crate::{storage, DisplayQuality, MusicVolume, SfxVolume, Volume};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_KEY: &str = "settings.json";
+
+// Inserts the saved settings as resources, so they are there before the menu is shown
+pub fn settings_plugin(app: &mut App) {
+    let settings = Settings::load();
+    app.insert_resource(settings.display_quality)
+        .insert_resource(settings.volume)
+        .insert_resource(settings.music_volume)
+        .insert_resource(settings.sfx_volume)
+        .add_systems(
+            Update,
+            save_settings.run_if(
+                resource_changed::<DisplayQuality>
+                    .or(resource_changed::<Volume>)
+                    .or(resource_changed::<MusicVolume>)
+                    .or(resource_changed::<SfxVolume>),
+            ),
+        );
+}
+
+// What gets written to storage, every setting resource has a field here
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub display_quality: DisplayQuality,
+    pub volume: Volume,
+    pub music_volume: MusicVolume,
+    pub sfx_volume: SfxVolume,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            display_quality: DisplayQuality::Medium,
+            volume: Volume(7),
+            music_volume: MusicVolume(7),
+            sfx_volume: SfxVolume(7),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let Some(contents) = storage::load(SETTINGS_KEY) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring saved settings: {err}");
+            Self::default()
+        })
+    }
+}
+
+fn save_settings(
+    display_quality: Res<DisplayQuality>,
+    volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    sfx_volume: Res<SfxVolume>,
+) {
+    // Loading them at startup isn't a change worth writing back, only changes made since are
+    let changed_since_load = [
+        display_quality.is_changed() && !display_quality.is_added(),
+        volume.is_changed() && !volume.is_added(),
+        music_volume.is_changed() && !music_volume.is_added(),
+        sfx_volume.is_changed() && !sfx_volume.is_added(),
+    ];
+    if !changed_since_load.contains(&true) {
+        return;
+    }
+    let settings = Settings {
+        display_quality: *display_quality,
+        volume: *volume,
+        music_volume: *music_volume,
+        sfx_volume: *sfx_volume,
+    };
+    match serde_json::to_string_pretty(&settings) {
+        Ok(contents) => {
+            if let Err(err) = storage::save(SETTINGS_KEY, &contents) {
+                error!("Failed to save settings: {err}");
+            }
+        }
+        Err(err) => error!("Failed to serialize settings: {err}"),
+    }
+}
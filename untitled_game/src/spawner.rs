@@ -0,0 +1,244 @@
+//! Spawners keep dropping scenes into the level, with a cap on how many are alive at once and a
+//! policy for getting rid of them again. In Blender they are set up with a custom property on an
+//! empty, e.g. `{"spawner": {"scene": "untitled_game.glb#Scene0", "interval": 2, "max_alive": 5}}`
+
+use crate::extras::{Extras, ExtrasReader, ExtrasReady};
+use crate::game::{on_scene_spawn, OnGameScreen};
+use crate::menu::MenuState;
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn spawner_plugin(app: &mut App) {
+    app.add_observer(spawn_spawners)
+        .add_systems(Update, link_restored)
+        .add_systems(
+            Update,
+            (spawn, despawn)
+                .chain()
+                .run_if(in_state(MenuState::Disabled)),
+        );
+}
+
+#[derive(Component, Debug, Clone)]
+#[require(Transform)]
+pub struct Spawner {
+    pub scene: Handle<Scene>,
+    // Repeating, one spawn each time it finishes
+    pub interval: Timer,
+    // Nothing is spawned while this many of the spawner's entities are alive
+    pub max_alive: usize,
+    // Size of the box around the spawner the spawn positions are picked in
+    pub area: Vec3,
+    pub despawn: DespawnPolicy,
+}
+
+impl Spawner {
+    pub fn new(scene: Handle<Scene>) -> Self {
+        Self {
+            scene,
+            interval: Timer::from_seconds(1., TimerMode::Repeating),
+            max_alive: 10,
+            area: Vec3::ZERO,
+            despawn: DespawnPolicy::Never,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DespawnPolicy {
+    // Only the cap on alive entities limits them
+    Never,
+    // Seconds after being spawned
+    Lifetime(f32),
+    // Once one of its bodies falls below this height
+    KillPlane(f32),
+}
+
+// On the scene roots spawned by a spawner
+#[derive(Component, Debug)]
+pub struct Spawned {
+    pub spawner: Entity,
+    // Seconds since it was spawned, only counted while the game runs
+    pub age: f32,
+}
+
+// On spawned scene roots restored from a save, until the spawner with that name is there too
+#[derive(Component, Debug)]
+pub struct RestoredSpawned {
+    pub spawner: String,
+    pub age: f32,
+}
+
+// B stands for blender
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct BSpawnerExtra {
+    pub spawner: Option<BSpawner>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BSpawner {
+    // Asset path of the scene, with its label
+    pub scene: String,
+    // In seconds
+    pub interval: Option<f32>,
+    pub max_alive: Option<usize>,
+    // Custom properties in blender don't get their y and z swapped automatically like meshes
+    pub area: Option<Vec3>,
+    // Despawns after that many seconds, takes precedence over `kill_height`
+    pub lifetime: Option<f32>,
+    pub kill_height: Option<f32>,
+}
+
+pub(crate) fn spawn_scene<'a>(
+    commands: &'a mut Commands,
+    scene: Handle<Scene>,
+    transform: Transform,
+) -> EntityCommands<'a> {
+    let mut root = commands.spawn((SceneRoot(scene), transform, OnGameScreen));
+    root.observe(on_scene_spawn);
+    root
+}
+
+fn spawn_spawners(
+    trigger: Trigger<ExtrasReady>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    children: Query<&Children>,
+    extras: Query<&Extras>,
+    reader: ExtrasReader,
+) {
+    let root = trigger.entity();
+    for entity in children.iter_descendants(root) {
+        let Some(node) = extras
+            .get(entity)
+            .ok()
+            .and_then(|extras| extras.node.as_ref())
+        else {
+            continue;
+        };
+        let Some(BSpawnerExtra {
+            spawner: Some(data),
+        }) = reader.parse_value::<BSpawnerExtra>(root, entity, node)
+        else {
+            continue;
+        };
+
+        // Timers panic on negative durations, and zero would spawn every frame or despawn at once
+        let seconds = |property: &str, value: Option<f32>| {
+            let value = value?;
+            if value.is_finite() && value > 0. {
+                return Some(value);
+            }
+            let err =
+                format!("spawner {property} must be a positive number of seconds, not {value}");
+            reader.report(root, entity, err, node);
+            None
+        };
+        let interval = seconds("interval", data.interval);
+        let lifetime = seconds("lifetime", data.lifetime);
+
+        let mut spawner = Spawner::new(asset_server.load(data.scene));
+        if let Some(interval) = interval {
+            spawner.interval = Timer::from_seconds(interval, TimerMode::Repeating);
+        }
+        if let Some(max_alive) = data.max_alive {
+            spawner.max_alive = max_alive;
+        }
+        if let Some(area) = data.area {
+            spawner.area = Vec3::new(area.x, area.z, area.y);
+        }
+        spawner.despawn = match (lifetime, data.kill_height) {
+            (Some(lifetime), _) => DespawnPolicy::Lifetime(lifetime),
+            (None, Some(kill_height)) => DespawnPolicy::KillPlane(kill_height),
+            (None, None) => DespawnPolicy::Never,
+        };
+        commands.entity(entity).insert(spawner);
+    }
+}
+
+fn spawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut spawners: Query<(Entity, &mut Spawner, &GlobalTransform)>,
+    spawned: Query<&Spawned>,
+) {
+    let mut rng = rand::thread_rng();
+    for (entity, mut spawner, global_transform) in &mut spawners {
+        if !spawner.interval.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let alive = spawned
+            .iter()
+            .filter(|spawned| spawned.spawner == entity)
+            .count();
+        if alive >= spawner.max_alive {
+            continue;
+        }
+
+        let offset = Vec3::new(rng.gen(), rng.gen(), rng.gen()) - 0.5;
+        let (_, rotation, translation) = global_transform.to_scale_rotation_translation();
+        spawn_scene(
+            &mut commands,
+            spawner.scene.clone(),
+            Transform::from_translation(translation + offset * spawner.area)
+                .with_rotation(rotation),
+        )
+        .insert(Spawned {
+            spawner: entity,
+            age: 0.,
+        });
+    }
+}
+
+fn despawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    spawners: Query<&Spawner>,
+    mut spawned: Query<(Entity, &mut Spawned)>,
+    children: Query<&Children>,
+    bodies: Query<&GlobalTransform, With<RigidBody>>,
+) {
+    for (entity, mut spawned) in &mut spawned {
+        spawned.age += time.delta_secs();
+        // Entities of a despawned spawner are left alone
+        let Ok(spawner) = spawners.get(spawned.spawner) else {
+            continue;
+        };
+        let expired = match spawner.despawn {
+            DespawnPolicy::Never => false,
+            DespawnPolicy::Lifetime(lifetime) => spawned.age > lifetime,
+            // The scene root stays where it was spawned, its bodies are the ones falling
+            DespawnPolicy::KillPlane(height) => children
+                .iter_descendants(entity)
+                .filter_map(|child| bodies.get(child).ok())
+                .any(|transform| transform.translation().y < height),
+        };
+        if expired {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn link_restored(
+    mut commands: Commands,
+    restored: Query<(Entity, &RestoredSpawned)>,
+    spawners: Query<(Entity, &Name), With<Spawner>>,
+) {
+    for (entity, restored) in &restored {
+        if let Some((spawner, _)) = spawners
+            .iter()
+            .find(|(_, name)| name.as_str() == restored.spawner)
+        {
+            commands
+                .entity(entity)
+                .remove::<RestoredSpawned>()
+                .insert(Spawned {
+                    spawner,
+                    age: restored.age,
+                });
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Small persistent key value storage for player data like bindings and settings.
+//! Native builds write one file per key in the user's config directory, the web build uses the
+//! browser's local storage.
+
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+
+const APP_NAME: &str = "untitled_game";
+
+#[cfg(not(target_family = "wasm"))]
+fn config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+    base.join(APP_NAME)
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn load(key: &str) -> Option<String> {
+    std::fs::read_to_string(config_dir().join(key)).ok()
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn save(key: &str, contents: &str) -> Result<(), String> {
+    let dir = config_dir();
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    std::fs::write(dir.join(key), contents).map_err(|err| err.to_string())
+}
+
+#[cfg(target_family = "wasm")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_family = "wasm")]
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?
+        .get_item(&format!("{APP_NAME}/{key}"))
+        .ok()?
+}
+
+#[cfg(target_family = "wasm")]
+pub fn save(key: &str, contents: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or_else(|| String::from("local storage is not available"))?
+        .set_item(&format!("{APP_NAME}/{key}"), contents)
+        .map_err(|err| format!("{err:?}"))
+}
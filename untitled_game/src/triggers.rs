@@ -0,0 +1,153 @@
+//! Volumes placed in Blender as cube empties, so they are moved, rotated and scaled there like
+//! any other object. A trigger fires when the player walks in, e.g.
+//! `{"trigger": {"targets": ["Door"], "once": true}}` opens a door the first time, and a camera
+//! hint changes the third person camera while the player is inside, e.g.
+//! `{"camera_hint": {"distance": 3}}` for a narrow corridor.
+
+use crate::cameras::third_person::CameraHint;
+use crate::extras::{Extras, ExtrasReader, ExtrasReady};
+use crate::interaction::Interacted;
+use crate::menu::MenuState;
+use crate::Player;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn triggers_plugin(app: &mut App) {
+    app.add_observer(spawn_volumes).add_systems(
+        Update,
+        detect_triggers.run_if(in_state(MenuState::Disabled)),
+    );
+}
+
+// Interacts with its targets when the player enters it, like a switch the player walks into
+#[derive(Component, Debug)]
+pub struct TriggerVolume {
+    pub targets: Vec<Entity>,
+    // Only interacts with its targets the first time the player enters
+    pub once: bool,
+    pub fired: bool,
+    // Whether the player was inside on the last frame
+    pub occupied: bool,
+}
+
+// Triggered on a `TriggerVolume` entity when the player enters it
+#[derive(Event, Debug)]
+pub struct TriggerEntered {
+    pub by: Entity,
+}
+
+// Triggered on a `TriggerVolume` entity when the player leaves it
+#[derive(Event, Debug)]
+pub struct TriggerExited {
+    pub by: Entity,
+}
+
+// B stands for blender
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct BVolumeExtra {
+    pub trigger: Option<BTrigger>,
+    pub camera_hint: Option<BCameraHint>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BTrigger {
+    // Names of the objects it interacts with, in the same scene
+    #[serde(default)]
+    pub targets: Vec<String>,
+    #[serde(default)]
+    pub once: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BCameraHint {
+    pub distance: f32,
+}
+
+// Blender draws a cube empty from -1 to 1 on each axis before its transform
+pub(crate) fn in_volume(transform: &GlobalTransform, point: Vec3) -> bool {
+    let local = transform.affine().inverse().transform_point3(point);
+    local.abs().cmple(Vec3::ONE).all()
+}
+
+fn spawn_volumes(
+    trigger: Trigger<ExtrasReady>,
+    mut commands: Commands,
+    children: Query<&Children>,
+    extras: Query<&Extras>,
+    names: Query<(Entity, &Name)>,
+    reader: ExtrasReader,
+) {
+    let root = trigger.entity();
+    for entity in children.iter_descendants(root) {
+        let Some(node) = extras
+            .get(entity)
+            .ok()
+            .and_then(|extras| extras.node.as_ref())
+        else {
+            continue;
+        };
+        let Some(data) = reader.parse_value::<BVolumeExtra>(root, entity, node) else {
+            continue;
+        };
+
+        let mut summary = Vec::new();
+        if let Some(volume) = data.trigger {
+            let targets = children
+                .iter_descendants(root)
+                .filter_map(|child| names.get(child).ok())
+                .filter(|(_, name)| volume.targets.iter().any(|target| target == name.as_str()))
+                .map(|(target, _)| target)
+                .collect::<Vec<Entity>>();
+            if targets.len() < volume.targets.len() {
+                warn!(
+                    "Some targets of trigger {entity} are not in its scene: {:?}",
+                    volume.targets
+                );
+            }
+            summary.push(format!("trigger for {:?}", volume.targets));
+            commands.entity(entity).insert(TriggerVolume {
+                targets,
+                once: volume.once,
+                fired: false,
+                occupied: false,
+            });
+        }
+        if let Some(hint) = data.camera_hint {
+            summary.push(format!("camera hint at distance {}", hint.distance));
+            commands.entity(entity).insert(CameraHint {
+                distance: hint.distance,
+            });
+        }
+        if !summary.is_empty() {
+            reader.summary(root, entity, &summary);
+        }
+    }
+}
+
+fn detect_triggers(
+    mut commands: Commands,
+    player: Single<(Entity, &GlobalTransform), With<Player>>,
+    mut triggers: Query<(Entity, &mut TriggerVolume, &GlobalTransform)>,
+) {
+    let (player, player_transform) = player.into_inner();
+    let position = player_transform.translation();
+    for (entity, mut trigger, transform) in &mut triggers {
+        let inside = in_volume(transform, position);
+        if inside == trigger.occupied {
+            continue;
+        }
+        trigger.occupied = inside;
+        if !inside {
+            commands.trigger_targets(TriggerExited { by: player }, entity);
+            continue;
+        }
+        commands.trigger_targets(TriggerEntered { by: player }, entity);
+        // Without targets the event would go to every `Interacted` observer
+        if trigger.targets.is_empty() || (trigger.once && trigger.fired) {
+            continue;
+        }
+        trigger.fired = true;
+        commands.trigger_targets(Interacted { by: player }, trigger.targets.clone());
+    }
+}
@@ -0,0 +1,19 @@
+use crate::{SnappedCoordinate, SolveStats};
+use bevy::prelude::Event;
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CellCollapsed {
+    pub coord: SnappedCoordinate,
+    pub tile: usize,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Contradiction {
+    pub coord: SnappedCoordinate,
+}
+
+/// Sent once every cell of the wave has collapsed
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SolveFinished {
+    pub stats: SolveStats,
+}
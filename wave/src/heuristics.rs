@@ -0,0 +1,142 @@
+use crate::{SnappedCoordinate, Tile, Wave};
+use rand::{Rng, RngCore};
+
+/// Decides which cell [`Wave::step`] collapses next
+pub trait Heuristic: Send + Sync {
+    /// `candidates` are never empty, every one of them is inside the active region, not collapsed
+    /// and has at least one option left
+    fn select(
+        &self,
+        wave: &Wave,
+        tiles: &[Tile],
+        candidates: &[SnappedCoordinate],
+        rng: &mut dyn RngCore,
+    ) -> SnappedCoordinate;
+}
+
+/// Fewest options left, random tie-break
+pub struct MinimumOptions;
+
+impl Heuristic for MinimumOptions {
+    fn select(
+        &self,
+        wave: &Wave,
+        _tiles: &[Tile],
+        candidates: &[SnappedCoordinate],
+        rng: &mut dyn RngCore,
+    ) -> SnappedCoordinate {
+        random_lowest(candidates, rng, |coord| {
+            wave.cell(coord).options.len() as f32
+        })
+    }
+}
+
+/// Lowest Shannon entropy based on [`Tile::weight`], random tie-break
+pub struct MinimumEntropy;
+
+impl Heuristic for MinimumEntropy {
+    fn select(
+        &self,
+        wave: &Wave,
+        tiles: &[Tile],
+        candidates: &[SnappedCoordinate],
+        rng: &mut dyn RngCore,
+    ) -> SnappedCoordinate {
+        random_lowest(candidates, rng, |coord| {
+            // Options without weight can't be picked, they don't add to the entropy
+            let weights = wave
+                .cell(coord)
+                .options
+                .iter()
+                .map(|option| tiles[*option].weight)
+                .filter(|weight| *weight > 0.)
+                .collect::<Vec<f32>>();
+            let sum = weights.iter().sum::<f32>();
+            if sum <= 0. {
+                return 0.;
+            }
+            let sum_log = weights
+                .iter()
+                .map(|weight| weight * weight.ln())
+                .sum::<f32>();
+            sum.ln() - sum_log / sum
+        })
+    }
+}
+
+/// Row by row starting at the bottom left, gives the output a strong directional structure
+pub struct Scanline;
+
+impl Heuristic for Scanline {
+    fn select(
+        &self,
+        wave: &Wave,
+        _tiles: &[Tile],
+        candidates: &[SnappedCoordinate],
+        _rng: &mut dyn RngCore,
+    ) -> SnappedCoordinate {
+        *candidates
+            .iter()
+            .min_by_key(|coord| wave.index(**coord))
+            .unwrap()
+    }
+}
+
+/// Ring by ring going outward from `center`
+pub struct Spiral {
+    pub center: SnappedCoordinate,
+}
+
+impl Heuristic for Spiral {
+    fn select(
+        &self,
+        _wave: &Wave,
+        _tiles: &[Tile],
+        candidates: &[SnappedCoordinate],
+        _rng: &mut dyn RngCore,
+    ) -> SnappedCoordinate {
+        let key = |coord: &SnappedCoordinate| {
+            let x = coord.x as f32 - self.center.x as f32;
+            let y = coord.y as f32 - self.center.y as f32;
+            (x.abs().max(y.abs()), y.atan2(x))
+        };
+        *candidates
+            .iter()
+            .min_by(|a, b| key(a).partial_cmp(&key(b)).unwrap())
+            .unwrap()
+    }
+}
+
+/// Any cell at all
+pub struct RandomOrder;
+
+impl Heuristic for RandomOrder {
+    fn select(
+        &self,
+        _wave: &Wave,
+        _tiles: &[Tile],
+        candidates: &[SnappedCoordinate],
+        rng: &mut dyn RngCore,
+    ) -> SnappedCoordinate {
+        candidates[rng.gen_range(0..candidates.len())]
+    }
+}
+
+fn random_lowest(
+    candidates: &[SnappedCoordinate],
+    rng: &mut dyn RngCore,
+    score: impl Fn(SnappedCoordinate) -> f32,
+) -> SnappedCoordinate {
+    let scores = candidates
+        .iter()
+        .map(|coord| score(*coord))
+        .collect::<Vec<f32>>();
+    let least = scores.iter().copied().fold(f32::INFINITY, f32::min);
+    let lowest = candidates
+        .iter()
+        .zip(scores)
+        .filter(|(_, score)| *score <= least + f32::EPSILON)
+        .map(|(coord, _)| *coord)
+        .collect::<Vec<SnappedCoordinate>>();
+    lowest[rng.gen_range(0..lowest.len())]
+}
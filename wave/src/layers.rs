@@ -0,0 +1,96 @@
+use crate::{NeighborFn, SnappedCoordinate, Tile, Wave};
+use rand::Rng;
+
+/// One layer of a [`LayeredWave`], e.g. terrain, decoration or objects
+pub struct Layer {
+    pub tiles: Vec<Tile>,
+    pub wave: Wave,
+}
+
+/// The tiles of `layer` listed in `tiles` may only be placed on cells where the `below` layer
+/// collapsed to one of `allowed`, e.g. trees only on grass
+pub struct LayerConstraint {
+    pub layer: usize,
+    pub tiles: Vec<usize>,
+    pub below: usize,
+    pub allowed: Vec<usize>,
+}
+
+/// Stacked waves sharing the same grid, each with their own tileset and adjacency rules.
+/// Layers are solved bottom to top so constraints can only look at the layers below them
+pub struct LayeredWave {
+    pub layers: Vec<Layer>,
+    pub constraints: Vec<LayerConstraint>,
+}
+
+impl LayeredWave {
+    pub fn new(
+        width: usize,
+        height: usize,
+        tilesets: Vec<Vec<Tile>>,
+        neighbors: NeighborFn,
+    ) -> Self {
+        Self {
+            layers: tilesets
+                .into_iter()
+                .map(|tiles| Layer {
+                    wave: Wave::new(width, height, tiles.len(), neighbors),
+                    tiles,
+                })
+                .collect(),
+            constraints: Vec::new(),
+        }
+    }
+
+    /// The tile a cell of a layer collapsed to
+    pub fn tile(&self, layer: usize, coord: SnappedCoordinate) -> Option<usize> {
+        let cell = self.layers[layer].wave.cell(coord);
+        cell.collapsed.then(|| cell.options[0])
+    }
+
+    /// Solve every layer in order, returns false as soon as one of them runs out of attempts
+    pub fn solve(&mut self, rng: &mut impl Rng, max_attempts: usize) -> bool {
+        for layer in 0..self.layers.len() {
+            self.constrain_layer(layer);
+            let Layer { tiles, wave } = &mut self.layers[layer];
+            if !wave.solve(tiles, rng, max_attempts) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Reset a layer and restrict its cells based on the already solved layers below it
+    pub fn constrain_layer(&mut self, layer: usize) {
+        let mut restrictions = Vec::new();
+        for constraint in self
+            .constraints
+            .iter()
+            .filter(|constraint| constraint.layer == layer)
+        {
+            assert!(
+                constraint.below < layer,
+                "layer {layer} can only be constrained by the layers below it"
+            );
+            for coord in self.layers[layer].wave.coords() {
+                if self
+                    .tile(constraint.below, coord)
+                    .is_some_and(|below| constraint.allowed.contains(&below))
+                {
+                    continue;
+                }
+                let allowed = (0..self.layers[layer].tiles.len())
+                    .filter(|tile| !constraint.tiles.contains(tile))
+                    .collect::<Vec<usize>>();
+                restrictions.push((coord, allowed));
+            }
+        }
+
+        let Layer { tiles, wave } = &mut self.layers[layer];
+        wave.clear_restrictions();
+        wave.reset_all(tiles);
+        for (coord, allowed) in restrictions {
+            wave.restrict(coord, &allowed, tiles);
+        }
+    }
+}
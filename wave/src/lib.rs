@@ -1,2 +1,10 @@
+mod events;
+mod heuristics;
+mod layers;
+mod solver;
 mod wfc;
-pub use wfc::*;
\ No newline at end of file
+pub use events::*;
+pub use heuristics::*;
+pub use layers::*;
+pub use solver::*;
+pub use wfc::*;
@@ -7,15 +7,15 @@ use wave::*;
 
 const DIM: usize = 30;
 const TILE_SIZE: f32 = 56.;
+/// Width and height of the region rerolled when clicking a cell
+const REROLL_SIZE: usize = 6;
 
 #[derive(Component)]
 struct Collapsed;
 
 #[derive(Component)]
-struct Cell {
-    pub options: Vec<usize>,
+struct CellSprite {
     pub coord: SnappedCoordinate,
-    pub collapsed: bool,
 }
 
 #[derive(Resource)]
@@ -23,6 +23,15 @@ struct TileConfig {
     pub tiles: Vec<Tile>,
 }
 
+#[derive(Resource)]
+struct Grid {
+    pub wave: Wave,
+    /// Sprite entities, indexed the same way as `wave.cells`
+    pub entities: Vec<Entity>,
+    /// Whether `SolveFinished` was already sent for the current solve
+    pub finished: bool,
+}
+
 fn main() {
     App::new()
         .insert_resource(TileConfig { tiles: Vec::new() })
@@ -37,8 +46,14 @@ fn main() {
             }),
             ..default()
         }))
+        .add_event::<CellCollapsed>()
+        .add_event::<Contradiction>()
+        .add_event::<SolveFinished>()
         .add_systems(Startup, (preload_tiles, spawn_cells).chain())
-        .add_systems(Update, analyze_tiles)
+        .add_systems(
+            Update,
+            (switch_heuristic, reroll_region, analyze_tiles, log_stats).chain(),
+        )
         .run();
 }
 
@@ -219,99 +234,179 @@ fn spawn_cells(mut commands: Commands, tiles: Res<TileConfig>, window: Single<&W
         ),
     ));
 
-    for x in 0..DIM {
-        for y in 0..DIM {
-            commands.spawn((
-                Transform::from_xyz(
-                    x as f32 * TILE_SIZE + TILE_SIZE / 2.,
-                    y as f32 * TILE_SIZE + TILE_SIZE / 2.,
-                    0.,
-                ),
-                Cell {
-                    options: (0..tiles.tiles.len()).collect(),
-                    coord: SnappedCoordinate { x, y },
-                    collapsed: false,
-                },
-            ));
-        }
-    }
+    let wave = Wave::new(DIM, DIM, tiles.tiles.len(), get_neighbors_no_wrap);
+    let entities = wave
+        .coords()
+        .map(|coord| {
+            commands
+                .spawn((
+                    Transform::from_xyz(
+                        coord.x as f32 * TILE_SIZE + TILE_SIZE / 2.,
+                        coord.y as f32 * TILE_SIZE + TILE_SIZE / 2.,
+                        0.,
+                    ),
+                    CellSprite { coord },
+                ))
+                .id()
+        })
+        .collect();
+    commands.insert_resource(Grid {
+        wave,
+        entities,
+        finished: false,
+    });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn analyze_tiles(
-    mut query: Query<(&mut Transform, &mut Cell, Entity), Without<Collapsed>>,
-    mut collapsed: Query<(&mut Transform, &mut Cell, Entity), With<Collapsed>>,
+    mut grid: ResMut<Grid>,
+    mut transforms: Query<&mut Transform>,
+    collapsed: Query<(Entity, &CellSprite), With<Collapsed>>,
     mut commands: Commands,
     tiles: Res<TileConfig>,
+    mut collapsed_events: EventWriter<CellCollapsed>,
+    mut contradiction_events: EventWriter<Contradiction>,
+    mut finished_events: EventWriter<SolveFinished>,
 ) {
-    if query.is_empty() {
+    if grid.finished {
         return;
     }
 
-    let mut sorted = query
-        .iter()
-        .map(|(_, cell, entity)| (entity, cell.options.len(), cell.coord))
-        .collect::<Vec<(Entity, usize, SnappedCoordinate)>>();
-    sorted.sort_by(|a, b| a.1.cmp(&b.1));
-    let end_index = if let Some((i, _)) = sorted
-        .iter()
-        .enumerate()
-        .skip(1)
-        .find(|(_, (_, options_len, _))| *options_len > sorted[0].1)
-    {
-        i
+    match grid.wave.step(&tiles.tiles, &mut thread_rng()) {
+        Step::Collapsed { coord, tile } => {
+            collapsed_events.send(CellCollapsed { coord, tile });
+            let tile = &tiles.tiles[tile];
+            let entity = grid.entities[grid.wave.index(coord)];
+            if let Ok(mut transform) = transforms.get_mut(entity) {
+                transform.rotate_local_z(-FRAC_PI_2 * tile.rotation as f32);
+            }
+            commands
+                .entity(entity)
+                .insert((Collapsed, Sprite::from_image(tile.image.clone())));
+        }
+        Step::Contradiction(coord) => {
+            contradiction_events.send(Contradiction { coord });
+            grid.wave.reset(&tiles.tiles);
+            clear_sprites(&grid.wave, &collapsed, &mut transforms, &mut commands);
+        }
+        Step::Finished => {
+            grid.finished = true;
+            finished_events.send(SolveFinished {
+                stats: grid.wave.stats,
+            });
+        }
+    }
+}
+
+fn log_stats(mut finished_events: EventReader<SolveFinished>) {
+    for SolveFinished { stats } in finished_events.read() {
+        info!(
+            "Solved in {:?} over {} attempt(s): {} collapses, {} propagations, {} contradictions, final entropy {}",
+            stats.elapsed,
+            stats.attempts(),
+            stats.collapses,
+            stats.propagations,
+            stats.contradictions,
+            stats.final_entropy,
+        );
+    }
+}
+
+// Number keys pick a different heuristic and start over with it
+fn switch_heuristic(
+    mut grid: ResMut<Grid>,
+    mut transforms: Query<&mut Transform>,
+    collapsed: Query<(Entity, &CellSprite), With<Collapsed>>,
+    mut commands: Commands,
+    tiles: Res<TileConfig>,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    let heuristic: Box<dyn Heuristic> = if key_input.just_pressed(KeyCode::Digit1) {
+        Box::new(MinimumOptions)
+    } else if key_input.just_pressed(KeyCode::Digit2) {
+        Box::new(MinimumEntropy)
+    } else if key_input.just_pressed(KeyCode::Digit3) {
+        Box::new(Scanline)
+    } else if key_input.just_pressed(KeyCode::Digit4) {
+        Box::new(Spiral {
+            center: SnappedCoordinate {
+                x: DIM / 2,
+                y: DIM / 2,
+            },
+        })
+    } else if key_input.just_pressed(KeyCode::Digit5) {
+        Box::new(RandomOrder)
     } else {
-        sorted.len()
+        return;
     };
-    let chosen_index = thread_rng().gen_range(0..end_index);
-    let (entity, _, coords) = sorted[chosen_index];
 
-    let (mut transform, mut cell, entity) = query.get_mut(entity).unwrap();
+    grid.wave.heuristic = heuristic;
+    grid.wave.reset_stats();
+    grid.wave.reset_all(&tiles.tiles);
+    grid.finished = false;
+    clear_sprites(&grid.wave, &collapsed, &mut transforms, &mut commands);
+}
 
-    if cell.options.is_empty() {
-        for (_, mut cell, _) in &mut query {
-            cell.options = (0..tiles.tiles.len()).collect();
-        }
-        for (mut transform, mut cell, entity) in &mut collapsed {
-            cell.options = (0..tiles.tiles.len()).collect();
-            cell.collapsed = false;
-            commands
-                .entity(entity)
-                .remove::<Collapsed>()
-                .remove::<Sprite>();
-            transform.rotation = Quat::from_rotation_z(0.);
-        }
+// Clicking a cell rerolls the area around it, leaving the rest of the board as is
+#[allow(clippy::too_many_arguments)]
+fn reroll_region(
+    mut grid: ResMut<Grid>,
+    mut transforms: Query<&mut Transform>,
+    collapsed: Query<(Entity, &CellSprite), With<Collapsed>>,
+    mut commands: Commands,
+    tiles: Res<TileConfig>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+) {
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (camera, camera_transform) = *camera;
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    if position.x < 0. || position.y < 0. {
+        return;
+    }
+    let x = (position.x / TILE_SIZE) as usize;
+    let y = (position.y / TILE_SIZE) as usize;
+    if x >= DIM || y >= DIM {
         return;
     }
 
-    let tile_index = cell.options[thread_rng().gen_range(0..cell.options.len())];
-    let tile = &tiles.tiles[tile_index];
-
-    // Mark as collapsed
-    cell.options = vec![tile_index];
-    cell.collapsed = true;
-    transform.rotate_local_z(-FRAC_PI_2 * tile.rotation as f32);
-    commands
-        .entity(entity)
-        .insert((Collapsed, Sprite::from_image(tile.image.clone())));
+    let region = Region {
+        x: x.saturating_sub(REROLL_SIZE / 2),
+        y: y.saturating_sub(REROLL_SIZE / 2),
+        width: REROLL_SIZE,
+        height: REROLL_SIZE,
+    };
+    grid.wave.reset_stats();
+    grid.wave.unsolve_region(region, &tiles.tiles);
+    grid.finished = false;
+    clear_sprites(&grid.wave, &collapsed, &mut transforms, &mut commands);
+}
 
-    // check neighboring cells
-    let neighbors = get_neighbors_no_wrap(coords.x, coords.y, DIM - 1, DIM - 1);
-    for (_, mut cell, _) in &mut query {
-        let cb = |option| {
-            if cell.options.contains(option) {
-                Some(*option)
-            } else {
-                None
-            }
-        };
-        if Some(cell.coord) == neighbors.up {
-            cell.options = tile.up.iter().filter_map(cb).collect();
-        } else if Some(cell.coord) == neighbors.right {
-            cell.options = tile.right.iter().filter_map(cb).collect();
-        } else if Some(cell.coord) == neighbors.down {
-            cell.options = tile.down.iter().filter_map(cb).collect();
-        } else if Some(cell.coord) == neighbors.left {
-            cell.options = tile.left.iter().filter_map(cb).collect();
+// Remove the sprite of every cell that is no longer collapsed in the wave
+fn clear_sprites(
+    wave: &Wave,
+    collapsed: &Query<(Entity, &CellSprite), With<Collapsed>>,
+    transforms: &mut Query<&mut Transform>,
+    commands: &mut Commands,
+) {
+    for (entity, cell) in collapsed {
+        if wave.cell(cell.coord).collapsed {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .remove::<Collapsed>()
+            .remove::<Sprite>();
+        if let Ok(mut transform) = transforms.get_mut(entity) {
+            transform.rotation = Quat::from_rotation_z(0.);
         }
     }
 }
@@ -0,0 +1,393 @@
+use crate::{Direction, Heuristic, MinimumOptions, NeighborFn, SnappedCoordinate, Tile};
+use bevy::utils::{Duration, Instant};
+use rand::Rng;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    /// represents valid indices into tiles array
+    pub options: Vec<usize>,
+    pub collapsed: bool,
+}
+
+/// A rectangle of cells, `x` and `y` being the bottom left corner
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub fn contains(&self, coord: SnappedCoordinate) -> bool {
+        coord.x >= self.x
+            && coord.x < self.x + self.width
+            && coord.y >= self.y
+            && coord.y < self.y + self.height
+    }
+
+    pub fn coords(&self) -> impl Iterator<Item = SnappedCoordinate> {
+        let Region {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| SnappedCoordinate { x, y }))
+    }
+}
+
+/// What happened during a single [`Wave::step`]
+#[derive(PartialEq, Copy, Clone)]
+pub enum Step {
+    Collapsed {
+        coord: SnappedCoordinate,
+        tile: usize,
+    },
+    /// A cell ran out of options, the wave has to be reset before stepping again
+    Contradiction(SnappedCoordinate),
+    Finished,
+}
+
+/// Counters gathered while solving, cleared with [`Wave::reset_stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SolveStats {
+    pub collapses: usize,
+    /// Number of times a cell lost options because of a neighbor
+    pub propagations: usize,
+    pub contradictions: usize,
+    /// The solver starts over instead of backtracking, so this is also the number of backtracks
+    pub resets: usize,
+    /// Time spent inside [`Wave::step`] and [`Wave::reset`]
+    pub elapsed: Duration,
+    /// Entropy of the wave the last time [`Step::Finished`] was returned, zero once fully solved
+    pub final_entropy: f32,
+}
+
+impl SolveStats {
+    pub fn attempts(&self) -> usize {
+        self.resets + 1
+    }
+}
+
+pub struct Wave {
+    pub width: usize,
+    pub height: usize,
+    /// Row major, see [`Wave::index`]
+    pub cells: Vec<Cell>,
+    pub neighbors: NeighborFn,
+    /// Picks the next cell to collapse, defaults to [`MinimumOptions`]
+    pub heuristic: Box<dyn Heuristic>,
+    tile_count: usize,
+    /// When set, only cells inside of it are touched by the solver
+    region: Option<Region>,
+    /// Options a cell starts with after a reset, see [`Wave::restrict`]
+    restrictions: Vec<Option<Vec<usize>>>,
+    pub stats: SolveStats,
+}
+
+impl Wave {
+    pub fn new(width: usize, height: usize, tile_count: usize, neighbors: NeighborFn) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![
+                Cell {
+                    options: (0..tile_count).collect(),
+                    collapsed: false,
+                };
+                width * height
+            ],
+            neighbors,
+            heuristic: Box::new(MinimumOptions),
+            tile_count,
+            region: None,
+            restrictions: vec![None; width * height],
+            stats: SolveStats::default(),
+        }
+    }
+
+    pub fn index(&self, coord: SnappedCoordinate) -> usize {
+        coord.y * self.width + coord.x
+    }
+
+    pub fn cell(&self, coord: SnappedCoordinate) -> &Cell {
+        &self.cells[self.index(coord)]
+    }
+
+    pub fn coords(&self) -> impl Iterator<Item = SnappedCoordinate> {
+        Region {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+        .coords()
+    }
+
+    /// The region currently being regenerated, if any
+    pub fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.cells.iter().all(|cell| cell.collapsed)
+    }
+
+    /// Sum of every cell's entropy, assuming all of their options are equally likely
+    pub fn entropy(&self) -> f32 {
+        self.cells
+            .iter()
+            .map(|cell| (cell.options.len().max(1) as f32).log2())
+            .sum()
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = SolveStats::default();
+    }
+
+    // Largest coordinates handed to the `NeighborFn`, a wave without cells has no neighbors to
+    // look up but shouldn't underflow either
+    fn max_x(&self) -> usize {
+        self.width.saturating_sub(1)
+    }
+
+    fn max_y(&self) -> usize {
+        self.height.saturating_sub(1)
+    }
+
+    fn is_active(&self, coord: SnappedCoordinate) -> bool {
+        self.region.is_none_or(|region| region.contains(coord))
+    }
+
+    /// Start over after a contradiction, only the active region is reset if there is one
+    pub fn reset(&mut self, tiles: &[Tile]) {
+        let start = Instant::now();
+        match self.region {
+            Some(region) => self.unsolve_region(region, tiles),
+            None => self.reset_all(tiles),
+        }
+        self.stats.resets += 1;
+        self.stats.elapsed += start.elapsed();
+    }
+
+    /// Forget any active region and reset every cell
+    pub fn reset_all(&mut self, tiles: &[Tile]) {
+        self.region = None;
+        for index in 0..self.cells.len() {
+            self.cells[index].options = self.initial_options(index);
+            self.cells[index].collapsed = false;
+        }
+        let restricted = self
+            .coords()
+            .filter(|coord| self.restrictions[self.index(*coord)].is_some())
+            .collect();
+        self.propagate(tiles, restricted);
+    }
+
+    /// Only allow the given tiles at `coord`, this survives resets until [`Wave::clear_restrictions`]
+    pub fn restrict(&mut self, coord: SnappedCoordinate, allowed: &[usize], tiles: &[Tile]) {
+        let index = self.index(coord);
+        let restriction = self.initial_options(index);
+        self.restrictions[index] = Some(
+            restriction
+                .into_iter()
+                .filter(|option| allowed.contains(option))
+                .collect(),
+        );
+
+        let cell = &mut self.cells[index];
+        if !cell.collapsed {
+            cell.options.retain(|option| allowed.contains(option));
+            self.propagate(tiles, vec![coord]);
+        }
+    }
+
+    pub fn clear_restrictions(&mut self) {
+        self.restrictions = vec![None; self.width * self.height];
+    }
+
+    fn initial_options(&self, index: usize) -> Vec<usize> {
+        match &self.restrictions[index] {
+            Some(options) => options.clone(),
+            None => (0..self.tile_count).collect(),
+        }
+    }
+
+    /// Collapse the cell picked by the [`Heuristic`] and propagate the result
+    pub fn step(&mut self, tiles: &[Tile], rng: &mut impl Rng) -> Step {
+        let start = Instant::now();
+        let step = self.step_inner(tiles, rng);
+        self.stats.elapsed += start.elapsed();
+        match step {
+            Step::Collapsed { .. } => self.stats.collapses += 1,
+            Step::Contradiction(_) => self.stats.contradictions += 1,
+            Step::Finished => self.stats.final_entropy = self.entropy(),
+        }
+        step
+    }
+
+    fn step_inner(&mut self, tiles: &[Tile], rng: &mut impl Rng) -> Step {
+        let candidates = self
+            .coords()
+            .filter(|coord| self.is_active(*coord) && !self.cell(*coord).collapsed)
+            .collect::<Vec<SnappedCoordinate>>();
+        if candidates.is_empty() {
+            self.region = None;
+            return Step::Finished;
+        }
+        if let Some(coord) = candidates
+            .iter()
+            .find(|coord| self.cell(**coord).options.is_empty())
+        {
+            return Step::Contradiction(*coord);
+        }
+
+        let coord = self.heuristic.select(self, tiles, &candidates, rng);
+
+        // A cell that wraps around onto itself has to fit next to itself
+        let neighbors = (self.neighbors)(coord.x, coord.y, self.max_x(), self.max_y());
+        let loops = Direction::ALL
+            .into_iter()
+            .filter(|direction| neighbors.get(*direction) == Some(coord))
+            .collect::<Vec<Direction>>();
+        let index = self.index(coord);
+        let cell = &mut self.cells[index];
+        cell.options.retain(|option| {
+            loops
+                .iter()
+                .all(|direction| tiles[*option].neighbors(*direction).contains(option))
+        });
+        if cell.options.is_empty() {
+            return Step::Contradiction(coord);
+        }
+
+        // Weighted pick between the remaining options, or an even one if none of them have a
+        // weight, which `Tile::set_weight` prevents but the field can still be set directly
+        let total = cell
+            .options
+            .iter()
+            .map(|option| tiles[*option].weight.max(0.))
+            .sum::<f32>();
+        let tile = if total > 0. {
+            let mut roll = rng.gen_range(0.0..total);
+            *cell
+                .options
+                .iter()
+                .find(|option| {
+                    roll -= tiles[**option].weight.max(0.);
+                    roll < 0.
+                })
+                .unwrap_or(cell.options.last().unwrap())
+        } else {
+            cell.options[rng.gen_range(0..cell.options.len())]
+        };
+        cell.options = vec![tile];
+        cell.collapsed = true;
+        self.propagate(tiles, vec![coord]);
+
+        Step::Collapsed { coord, tile }
+    }
+
+    /// Step until every cell is collapsed, resetting on contradictions.
+    /// Returns false if `max_attempts` resets were not enough, see [`Wave::stats`] for the details
+    pub fn solve(&mut self, tiles: &[Tile], rng: &mut impl Rng, max_attempts: usize) -> bool {
+        self.reset_stats();
+        for _ in 0..max_attempts {
+            loop {
+                match self.step(tiles, rng) {
+                    Step::Collapsed { .. } => {}
+                    Step::Contradiction(_) => {
+                        self.reset(tiles);
+                        break;
+                    }
+                    Step::Finished => return true,
+                }
+            }
+        }
+        false
+    }
+
+    /// Restore every option of the cells in `region` and constrain them by the cells around it.
+    /// Until the region is solved, [`Wave::step`] leaves everything outside of it untouched
+    pub fn unsolve_region(&mut self, region: Region, tiles: &[Tile]) {
+        let x = region.x.min(self.width);
+        let y = region.y.min(self.height);
+        let region = Region {
+            x,
+            y,
+            width: region.width.min(self.width - x),
+            height: region.height.min(self.height - y),
+        };
+
+        // Restricted cells constrain their neighbors just like the fixed cells around the region
+        let mut border = Vec::new();
+        for coord in region.coords() {
+            let index = self.index(coord);
+            self.cells[index].options = self.initial_options(index);
+            self.cells[index].collapsed = false;
+            if self.restrictions[index].is_some() {
+                border.push(coord);
+            }
+        }
+        self.region = Some(region);
+
+        for coord in region.coords() {
+            let neighbors = (self.neighbors)(coord.x, coord.y, self.max_x(), self.max_y());
+            for neighbor in Direction::ALL
+                .iter()
+                .filter_map(|direction| neighbors.get(*direction))
+            {
+                if !region.contains(neighbor) && !border.contains(&neighbor) {
+                    border.push(neighbor);
+                }
+            }
+        }
+        self.propagate(tiles, border);
+    }
+
+    /// Reroll the cells in `region` while keeping the rest of the wave as is
+    pub fn regenerate_region(
+        &mut self,
+        region: Region,
+        tiles: &[Tile],
+        rng: &mut impl Rng,
+        max_attempts: usize,
+    ) -> bool {
+        self.unsolve_region(region, tiles);
+        self.solve(tiles, rng, max_attempts)
+    }
+
+    fn propagate(&mut self, tiles: &[Tile], mut stack: Vec<SnappedCoordinate>) {
+        while let Some(coord) = stack.pop() {
+            let neighbors = (self.neighbors)(coord.x, coord.y, self.max_x(), self.max_y());
+            for direction in Direction::ALL {
+                let Some(neighbor) = neighbors.get(direction) else {
+                    continue;
+                };
+                if !self.is_active(neighbor) || self.cell(neighbor).collapsed {
+                    continue;
+                }
+
+                let mut allowed = vec![false; self.tile_count];
+                for option in &self.cell(coord).options {
+                    for valid in tiles[*option].neighbors(direction) {
+                        allowed[*valid] = true;
+                    }
+                }
+                let options = &self.cell(neighbor).options;
+                let filtered = options
+                    .iter()
+                    .copied()
+                    .filter(|option| allowed[*option])
+                    .collect::<Vec<usize>>();
+                if filtered.len() != options.len() {
+                    let index = self.index(neighbor);
+                    self.cells[index].options = filtered;
+                    self.stats.propagations += 1;
+                    stack.push(neighbor);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::{Handle, Image};
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub struct SnappedCoordinate {
     pub x: usize,
     pub y: usize,
@@ -13,12 +13,44 @@ pub struct CardinalDirections {
     pub left: Option<SnappedCoordinate>,
 }
 
+impl CardinalDirections {
+    pub fn get(&self, direction: Direction) -> Option<SnappedCoordinate> {
+        match direction {
+            Direction::Up => self.up,
+            Direction::Right => self.right,
+            Direction::Down => self.down,
+            Direction::Left => self.left,
+        }
+    }
+}
+
+/// Matches the order of [`Tile::edges`]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+}
+
 #[derive(Clone)]
 pub struct Tile {
     pub image: Handle<Image>,
     /// 0-3 scaled by 90 degrees
     pub rotation: usize,
     pub edges: Vec<String>,
+    /// How likely this tile is to be picked relative to the others, defaults to 1.
+    /// Use [`Tile::set_weight`] to keep it positive
+    pub weight: f32,
     /// represents valid indices into tiles array
     pub up: Vec<usize>,
     pub left: Vec<usize>,
@@ -27,11 +59,15 @@ pub struct Tile {
 }
 
 impl Tile {
+    /// Lowest weight [`Tile::set_weight`] allows
+    pub const MIN_WEIGHT: f32 = 0.001;
+
     pub fn new(image: Handle<Image>, edges: Vec<String>) -> Self {
         Self {
             image,
             rotation: 0,
             edges,
+            weight: 1.,
             up: Vec::new(),
             right: Vec::new(),
             down: Vec::new(),
@@ -39,13 +75,20 @@ impl Tile {
         }
     }
 
+    /// Weights of zero or less, or NaN, are clamped to [`Tile::MIN_WEIGHT`], the solver can't
+    /// pick between options that all have no weight
+    pub fn set_weight(&mut self, weight: f32) {
+        self.weight = weight.max(Self::MIN_WEIGHT);
+    }
+
     pub fn rotate(&self, n: usize) -> Self {
         let mut edges = self.edges.clone();
-        edges.rotate_right(n);
+        edges.rotate_right(n % 4);
         Self {
             image: self.image.clone(),
-            rotation: self.rotation + n,
+            rotation: (self.rotation + n) % 4,
             edges,
+            weight: self.weight,
             up: Vec::new(),
             right: Vec::new(),
             down: Vec::new(),
@@ -53,6 +96,16 @@ impl Tile {
         }
     }
 
+    /// Indices of the tiles allowed next to this one in the given direction
+    pub fn neighbors(&self, direction: Direction) -> &[usize] {
+        match direction {
+            Direction::Up => &self.up,
+            Direction::Right => &self.right,
+            Direction::Down => &self.down,
+            Direction::Left => &self.left,
+        }
+    }
+
     pub fn generate_relationships(&mut self, tiles: &[Tile]) {
         for (i, tile) in tiles.iter().enumerate() {
             // Check if the current tile's bottom edge matches this tile's top edge
@@ -75,6 +128,9 @@ impl Tile {
     }
 }
 
+/// Signature shared by the `get_neighbors_*` functions, used to pick a wrap mode for a [`Wave`](crate::Wave)
+pub type NeighborFn = fn(usize, usize, usize, usize) -> CardinalDirections;
+
 pub fn get_neighbors_wrap_xy(x: usize, y: usize, x_max: usize, y_max: usize) -> CardinalDirections {
     let x_sub = if x == 0 { x_max } else { x - 1 };
     let y_sub = if y == 0 { y_max } else { y - 1 };
@@ -92,7 +148,8 @@ pub fn get_neighbors_wrap_x(x: usize, y: usize, x_max: usize, y_max: usize) -> C
     let mut directions = get_neighbors_wrap_xy(x, y, x_max, y_max);
     if y == 0 {
         directions.down = None;
-    } else if y == y_max {
+    }
+    if y == y_max {
         directions.up = None;
     }
     directions
@@ -102,7 +159,8 @@ pub fn get_neighbors_wrap_y(x: usize, y: usize, x_max: usize, y_max: usize) -> C
     let mut directions = get_neighbors_wrap_xy(x, y, x_max, y_max);
     if x == 0 {
         directions.left = None;
-    } else if x == x_max {
+    }
+    if x == x_max {
         directions.right = None;
     }
     directions
@@ -112,12 +170,14 @@ pub fn get_neighbors_no_wrap(x: usize, y: usize, x_max: usize, y_max: usize) ->
     let mut directions = get_neighbors_wrap_xy(x, y, x_max, y_max);
     if x == 0 {
         directions.left = None;
-    } else if x == x_max {
+    }
+    if x == x_max {
         directions.right = None;
     }
     if y == 0 {
         directions.down = None;
-    } else if y == y_max {
+    }
+    if y == y_max {
         directions.up = None;
     }
     directions
@@ -0,0 +1,99 @@
+// Every test binary only uses part of these helpers
+#![allow(dead_code)]
+
+use bevy::prelude::Handle;
+use wave::*;
+
+/// Same edges as the circuit tileset of the demo, without loading any image
+pub fn circuit_tiles() -> Vec<Tile> {
+    let mut tiles = vec![
+        Tile::new(Handle::default(), edges(["AAA", "AAA", "AAA", "AAA"])),
+        Tile::new(Handle::default(), edges(["BBB", "BBB", "BBB", "BBB"])),
+    ];
+    for (tile_edges, rotations) in [
+        (["BBB", "BCB", "BBB", "BBB"], 4),
+        (["BBB", "BDB", "BBB", "BDB"], 2),
+        (["ABB", "BCB", "BBA", "AAA"], 4),
+        (["ABB", "BBB", "BBB", "BBA"], 4),
+        (["BBB", "BCB", "BBB", "BCB"], 2),
+        (["BDB", "BCB", "BDB", "BCB"], 2),
+        (["BDB", "BBB", "BCB", "BBB"], 4),
+        (["BCB", "BCB", "BBB", "BCB"], 4),
+        (["BCB", "BCB", "BCB", "BCB"], 2),
+        (["BCB", "BCB", "BBB", "BBB"], 4),
+        (["BBB", "BCB", "BBB", "BCB"], 2),
+    ] {
+        let tile = Tile::new(Handle::default(), edges(tile_edges));
+        for i in 0..rotations {
+            tiles.push(tile.rotate(i));
+        }
+    }
+
+    let tiles_ref = &tiles.clone();
+    for tile in &mut tiles {
+        tile.generate_relationships(tiles_ref);
+    }
+    tiles
+}
+
+pub fn edges(edges: [&str; 4]) -> Vec<String> {
+    edges.into_iter().map(String::from).collect()
+}
+
+pub const NEIGHBOR_FNS: [NeighborFn; 4] = [
+    get_neighbors_no_wrap,
+    get_neighbors_wrap_x,
+    get_neighbors_wrap_y,
+    get_neighbors_wrap_xy,
+];
+
+pub fn heuristic(index: usize, wave: &Wave) -> Box<dyn Heuristic> {
+    match index % 5 {
+        0 => Box::new(MinimumOptions),
+        1 => Box::new(MinimumEntropy),
+        2 => Box::new(Scanline),
+        3 => Box::new(Spiral {
+            center: SnappedCoordinate {
+                x: wave.width / 2,
+                y: wave.height / 2,
+            },
+        }),
+        _ => Box::new(RandomOrder),
+    }
+}
+
+/// Panics if any two neighboring cells of a solved wave are not allowed next to each other
+pub fn assert_adjacency(wave: &Wave, tiles: &[Tile]) {
+    for coord in wave.coords() {
+        let cell = wave.cell(coord);
+        assert!(cell.collapsed, "{coord:?} is not collapsed");
+        assert_eq!(cell.options.len(), 1, "{coord:?} has {:?}", cell.options);
+        let tile = cell.options[0];
+        let neighbors = (wave.neighbors)(coord.x, coord.y, wave.width - 1, wave.height - 1);
+        for direction in Direction::ALL {
+            if let Some(neighbor) = neighbors.get(direction) {
+                let other = wave.cell(neighbor).options[0];
+                assert!(
+                    tiles[tile].neighbors(direction).contains(&other),
+                    "tile {other} at {neighbor:?} can't be {direction:?} of tile {tile} at {coord:?}"
+                );
+            }
+        }
+    }
+}
+
+/// One line per row, top row first
+pub fn render(wave: &Wave) -> String {
+    (0..wave.height)
+        .rev()
+        .map(|y| {
+            (0..wave.width)
+                .map(|x| {
+                    let cell = wave.cell(SnappedCoordinate { x, y });
+                    format!("{:>3}", cell.options[0])
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
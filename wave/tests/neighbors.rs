@@ -0,0 +1,93 @@
+use wave::*;
+
+const MAX: usize = 4;
+
+fn coord(x: usize, y: usize) -> Option<SnappedCoordinate> {
+    Some(SnappedCoordinate { x, y })
+}
+
+#[test]
+fn center_is_the_same_for_every_mode() {
+    for neighbors in [
+        get_neighbors_no_wrap,
+        get_neighbors_wrap_x,
+        get_neighbors_wrap_y,
+        get_neighbors_wrap_xy,
+    ] {
+        let directions = neighbors(2, 2, MAX, MAX);
+        assert_eq!(directions.up, coord(2, 3));
+        assert_eq!(directions.right, coord(3, 2));
+        assert_eq!(directions.down, coord(2, 1));
+        assert_eq!(directions.left, coord(1, 2));
+    }
+}
+
+#[test]
+fn wrap_xy_corners() {
+    let bottom_left = get_neighbors_wrap_xy(0, 0, MAX, MAX);
+    assert_eq!(bottom_left.down, coord(0, MAX));
+    assert_eq!(bottom_left.left, coord(MAX, 0));
+    assert_eq!(bottom_left.up, coord(0, 1));
+    assert_eq!(bottom_left.right, coord(1, 0));
+
+    let top_right = get_neighbors_wrap_xy(MAX, MAX, MAX, MAX);
+    assert_eq!(top_right.up, coord(MAX, 0));
+    assert_eq!(top_right.right, coord(0, MAX));
+    assert_eq!(top_right.down, coord(MAX, MAX - 1));
+    assert_eq!(top_right.left, coord(MAX - 1, MAX));
+}
+
+#[test]
+fn wrap_x_corners() {
+    let bottom_left = get_neighbors_wrap_x(0, 0, MAX, MAX);
+    assert_eq!(bottom_left.down, None);
+    assert_eq!(bottom_left.left, coord(MAX, 0));
+
+    let top_right = get_neighbors_wrap_x(MAX, MAX, MAX, MAX);
+    assert_eq!(top_right.up, None);
+    assert_eq!(top_right.right, coord(0, MAX));
+}
+
+#[test]
+fn wrap_y_corners() {
+    let bottom_left = get_neighbors_wrap_y(0, 0, MAX, MAX);
+    assert_eq!(bottom_left.down, coord(0, MAX));
+    assert_eq!(bottom_left.left, None);
+
+    let top_right = get_neighbors_wrap_y(MAX, MAX, MAX, MAX);
+    assert_eq!(top_right.up, coord(MAX, 0));
+    assert_eq!(top_right.right, None);
+}
+
+#[test]
+fn no_wrap_corners() {
+    let bottom_left = get_neighbors_no_wrap(0, 0, MAX, MAX);
+    assert_eq!(bottom_left.down, None);
+    assert_eq!(bottom_left.left, None);
+    assert_eq!(bottom_left.up, coord(0, 1));
+    assert_eq!(bottom_left.right, coord(1, 0));
+
+    let top_right = get_neighbors_no_wrap(MAX, MAX, MAX, MAX);
+    assert_eq!(top_right.up, None);
+    assert_eq!(top_right.right, None);
+    assert_eq!(top_right.down, coord(MAX, MAX - 1));
+    assert_eq!(top_right.left, coord(MAX - 1, MAX));
+}
+
+#[test]
+fn single_cell_only_neighbors_itself_when_wrapping() {
+    let cell = coord(0, 0);
+    let no_wrap = get_neighbors_no_wrap(0, 0, 0, 0);
+    assert_eq!(
+        (no_wrap.up, no_wrap.right, no_wrap.down, no_wrap.left),
+        (None, None, None, None)
+    );
+
+    let wrap_x = get_neighbors_wrap_x(0, 0, 0, 0);
+    assert_eq!((wrap_x.up, wrap_x.down), (None, None));
+    assert_eq!((wrap_x.left, wrap_x.right), (cell, cell));
+
+    let wrap_y = get_neighbors_wrap_y(0, 0, 0, 0);
+    assert_eq!((wrap_y.left, wrap_y.right), (None, None));
+    assert_eq!((wrap_y.up, wrap_y.down), (cell, cell));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::*;
+use rand::prelude::*;
+use wave::*;
+
+fn solve_seeded(neighbors: NeighborFn, heuristic_index: usize) -> String {
+    let tiles = circuit_tiles();
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut wave = Wave::new(8, 8, tiles.len(), neighbors);
+    wave.heuristic = heuristic(heuristic_index, &wave);
+    assert!(wave.solve(&tiles, &mut rng, 100));
+    render(&wave)
+}
+
+#[test]
+fn seeded_minimum_options() {
+    insta::assert_snapshot!(solve_seeded(get_neighbors_no_wrap, 0));
+}
+
+#[test]
+fn seeded_minimum_entropy_wrapping() {
+    insta::assert_snapshot!(solve_seeded(get_neighbors_wrap_xy, 1));
+}
+
+#[test]
+fn seeded_scanline() {
+    insta::assert_snapshot!(solve_seeded(get_neighbors_no_wrap, 2));
+}
+
+#[test]
+fn seeded_spiral() {
+    insta::assert_snapshot!(solve_seeded(get_neighbors_no_wrap, 3));
+}
+
+#[test]
+fn seeded_region_regeneration() {
+    let tiles = circuit_tiles();
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut wave = Wave::new(8, 8, tiles.len(), get_neighbors_no_wrap);
+    assert!(wave.solve(&tiles, &mut rng, 100));
+    let region = Region {
+        x: 2,
+        y: 2,
+        width: 4,
+        height: 4,
+    };
+    assert!(wave.regenerate_region(region, &tiles, &mut rng, 100));
+    insta::assert_snapshot!(render(&wave));
+}
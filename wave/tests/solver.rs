@@ -0,0 +1,177 @@
+mod common;
+
+use common::*;
+use proptest::prelude::*;
+use rand::prelude::*;
+use wave::*;
+
+const MAX_ATTEMPTS: usize = 1000;
+
+proptest! {
+    #[test]
+    fn solved_waves_satisfy_adjacency(
+        width in 1usize..10,
+        height in 1usize..10,
+        wrap in 0usize..4,
+        heuristic_index in 0usize..5,
+        seed in any::<u64>(),
+    ) {
+        let tiles = circuit_tiles();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut wave = Wave::new(width, height, tiles.len(), NEIGHBOR_FNS[wrap]);
+        wave.heuristic = heuristic(heuristic_index, &wave);
+
+        prop_assert!(wave.solve(&tiles, &mut rng, MAX_ATTEMPTS));
+        prop_assert!(wave.is_solved());
+        assert_adjacency(&wave, &tiles);
+        prop_assert!(wave.stats.collapses >= width * height);
+        prop_assert_eq!(wave.stats.final_entropy, 0.);
+    }
+
+    #[test]
+    fn regenerating_a_region_keeps_the_rest(
+        size in 4usize..10,
+        x in 0usize..10,
+        y in 0usize..10,
+        region_width in 1usize..6,
+        region_height in 1usize..6,
+        seed in any::<u64>(),
+    ) {
+        let tiles = circuit_tiles();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut wave = Wave::new(size, size, tiles.len(), get_neighbors_no_wrap);
+        prop_assert!(wave.solve(&tiles, &mut rng, MAX_ATTEMPTS));
+        let before = wave.cells.clone();
+
+        let region = Region { x, y, width: region_width, height: region_height };
+        wave.unsolve_region(region, &tiles);
+        let clamped = wave.region().unwrap();
+        for coord in wave.coords() {
+            prop_assert_eq!(wave.cell(coord).collapsed, !clamped.contains(coord));
+        }
+
+        prop_assert!(wave.solve(&tiles, &mut rng, MAX_ATTEMPTS));
+        prop_assert_eq!(wave.region(), None);
+        assert_adjacency(&wave, &tiles);
+        for coord in wave.coords().filter(|coord| !clamped.contains(*coord)) {
+            prop_assert_eq!(&wave.cell(coord).options, &before[wave.index(coord)].options);
+        }
+    }
+
+    #[test]
+    fn restrictions_survive_resets(seed in any::<u64>()) {
+        let tiles = circuit_tiles();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut wave = Wave::new(6, 6, tiles.len(), get_neighbors_no_wrap);
+        let corner = SnappedCoordinate { x: 0, y: 0 };
+        wave.restrict(corner, &[0], &tiles);
+        wave.reset_all(&tiles);
+
+        prop_assert!(wave.solve(&tiles, &mut rng, MAX_ATTEMPTS));
+        prop_assert_eq!(&wave.cell(corner).options, &vec![0]);
+        assert_adjacency(&wave, &tiles);
+    }
+}
+
+#[test]
+fn empty_options_are_a_contradiction() {
+    let tiles = circuit_tiles();
+    let mut wave = Wave::new(3, 3, tiles.len(), get_neighbors_no_wrap);
+    let center = SnappedCoordinate { x: 1, y: 1 };
+    wave.restrict(center, &[], &tiles);
+
+    // The center and every cell next to it are left without options
+    assert!(matches!(
+        wave.step(&tiles, &mut StdRng::seed_from_u64(0)),
+        Step::Contradiction(_)
+    ));
+    assert!(wave.cell(center).options.is_empty());
+    assert!(!wave.solve(&tiles, &mut StdRng::seed_from_u64(0), 3));
+    assert_eq!(wave.stats.resets, 3);
+}
+
+#[test]
+fn zero_sized_waves_are_solved_right_away() {
+    let tiles = circuit_tiles();
+    let mut rng = StdRng::seed_from_u64(0);
+    for (width, height) in [(0, 4), (4, 0), (0, 0)] {
+        let mut wave = Wave::new(width, height, tiles.len(), get_neighbors_no_wrap);
+        assert!(matches!(wave.step(&tiles, &mut rng), Step::Finished));
+        wave.unsolve_region(
+            Region {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 2,
+            },
+            &tiles,
+        );
+        assert!(wave.solve(&tiles, &mut rng, MAX_ATTEMPTS));
+        assert!(wave.is_solved());
+    }
+}
+
+#[test]
+fn weightless_tiles_are_picked_evenly() {
+    let mut tiles = circuit_tiles();
+    for tile in &mut tiles {
+        tile.weight = 0.;
+    }
+    for heuristic_index in 0..5 {
+        let mut rng = StdRng::seed_from_u64(heuristic_index as u64);
+        let mut wave = Wave::new(6, 6, tiles.len(), get_neighbors_no_wrap);
+        wave.heuristic = heuristic(heuristic_index, &wave);
+        assert!(wave.solve(&tiles, &mut rng, MAX_ATTEMPTS));
+        assert_adjacency(&wave, &tiles);
+    }
+}
+
+#[test]
+fn scanline_collapses_row_by_row() {
+    let tiles = circuit_tiles();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut wave = Wave::new(4, 4, tiles.len(), get_neighbors_no_wrap);
+    wave.heuristic = Box::new(Scanline);
+
+    let mut order = Vec::new();
+    loop {
+        match wave.step(&tiles, &mut rng) {
+            Step::Collapsed { coord, .. } => order.push(wave.index(coord)),
+            Step::Contradiction(_) => {
+                wave.reset(&tiles);
+                order.clear();
+            }
+            Step::Finished => break,
+        }
+    }
+    assert_eq!(order, (0..16).collect::<Vec<usize>>());
+}
+
+#[test]
+fn layer_constraints_are_respected() {
+    let tiles = circuit_tiles();
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut layers = LayeredWave::new(
+        8,
+        8,
+        vec![tiles.clone(), tiles.clone()],
+        get_neighbors_no_wrap,
+    );
+    // Tile 0 on the top layer only over tile 1 of the bottom layer
+    layers.constraints.push(LayerConstraint {
+        layer: 1,
+        tiles: vec![0],
+        below: 0,
+        allowed: vec![1],
+    });
+
+    assert!(layers.solve(&mut rng, MAX_ATTEMPTS));
+    for coord in layers.layers[1].wave.coords() {
+        if layers.tile(1, coord) == Some(0) {
+            assert_eq!(layers.tile(0, coord), Some(1), "{coord:?}");
+        }
+    }
+    for layer in &layers.layers {
+        assert_adjacency(&layer.wave, &layer.tiles);
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use bevy::prelude::Handle;
+use common::*;
+use wave::*;
+
+#[test]
+fn rotate_shifts_edges_clockwise() {
+    let tile = Tile::new(Handle::default(), edges(["ABC", "DEF", "GHI", "JKL"]));
+    let rotated = tile.rotate(1);
+    assert_eq!(rotated.edges, edges(["JKL", "ABC", "DEF", "GHI"]));
+    assert_eq!(rotated.rotation, 1);
+}
+
+#[test]
+fn rotate_round_trips() {
+    let tile = Tile::new(Handle::default(), edges(["ABC", "DEF", "GHI", "JKL"]));
+    for n in 0..8 {
+        let back = tile.rotate(n).rotate(4 - n % 4);
+        assert_eq!(back.edges, tile.edges, "rotating by {n}");
+        assert_eq!(back.rotation, 0, "rotating by {n}");
+    }
+    assert_eq!(tile.rotate(4).edges, tile.edges);
+    assert_eq!(tile.rotate(2).rotate(2).rotation, 0);
+}
+
+#[test]
+fn rotate_keeps_weight() {
+    let mut tile = Tile::new(Handle::default(), edges(["A", "B", "C", "D"]));
+    tile.weight = 3.;
+    assert_eq!(tile.rotate(3).weight, 3.);
+}
+
+#[test]
+fn set_weight_keeps_weights_positive() {
+    let mut tile = Tile::new(Handle::default(), edges(["A", "B", "C", "D"]));
+    for weight in [0., -2., f32::NAN] {
+        tile.set_weight(weight);
+        assert_eq!(tile.weight, Tile::MIN_WEIGHT, "setting {weight}");
+    }
+    tile.set_weight(0.5);
+    assert_eq!(tile.weight, 0.5);
+}
+
+#[test]
+fn relationships_match_reversed_edges() {
+    let mut tiles = vec![
+        Tile::new(Handle::default(), edges(["ABB", "BBB", "BBB", "BBA"])),
+        Tile::new(Handle::default(), edges(["BBB", "BBB", "BBA", "BBB"])),
+        Tile::new(Handle::default(), edges(["CCC", "CCC", "CCC", "CCC"])),
+    ];
+    let tiles_ref = &tiles.clone();
+    for tile in &mut tiles {
+        tile.generate_relationships(tiles_ref);
+    }
+
+    // "ABB" read the other way around is "BBA", the bottom edge of tile 1
+    assert_eq!(tiles[0].up, vec![1]);
+    assert_eq!(tiles[1].down, vec![0]);
+    assert_eq!(tiles[0].right, vec![1]);
+    assert_eq!(tiles[0].left, Vec::<usize>::new());
+    // Tile 2 only fits next to itself
+    assert_eq!(tiles[2].up, vec![2]);
+    assert_eq!(tiles[2].right, vec![2]);
+    assert_eq!(tiles[2].neighbors(Direction::Down), &[2]);
+}
+
+#[test]
+fn relationships_are_symmetric() {
+    let tiles = circuit_tiles();
+    for (i, tile) in tiles.iter().enumerate() {
+        for j in &tile.up {
+            assert!(tiles[*j].down.contains(&i), "{i} up {j}");
+        }
+        for j in &tile.right {
+            assert!(tiles[*j].left.contains(&i), "{i} right {j}");
+        }
+    }
+}